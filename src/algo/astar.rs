@@ -0,0 +1,123 @@
+use crate::maze::{Cell, CoordLookup, LinkStore};
+use crate::util::path::Path;
+use crate::util::Index;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Estimates the number of steps between two cells for [A* search][astar].
+///
+/// The estimate must never be larger than the real number of steps, otherwise the path found
+/// may not be the shortest one.  Estimates based on coordinates are only reliable for grids
+/// where coordinates reflect distance, so grids that wrap around or fold over edges should use
+/// [`Dijkstra`] instead.
+///
+/// [astar]: https://en.wikipedia.org/wiki/A*_search_algorithm
+pub trait Heuristic<G: CoordLookup> {
+    fn estimate(&self, grid: &G, from: Index, to: Index) -> usize;
+}
+
+/// Sum of the horizontal and vertical distance, the exact distance on an open square grid.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Manhattan;
+impl<G: CoordLookup> Heuristic<G> for Manhattan {
+    fn estimate(&self, grid: &G, from: Index, to: Index) -> usize {
+        let (a, b) = (grid.get_coords(from), grid.get_coords(to));
+        a.x().abs_diff(*b.x()) + a.y().abs_diff(*b.y())
+    }
+}
+
+/// Straight line distance, rounded down.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Euclidean;
+impl<G: CoordLookup> Heuristic<G> for Euclidean {
+    fn estimate(&self, grid: &G, from: Index, to: Index) -> usize {
+        let (a, b) = (grid.get_coords(from), grid.get_coords(to));
+        let dx = a.x().abs_diff(*b.x()) as f64;
+        let dy = a.y().abs_diff(*b.y()) as f64;
+        dx.hypot(dy) as usize
+    }
+}
+
+/// Always estimates zero, which turns A* into Dijkstra's algorithm.  Useful for grids where
+/// coordinates do not reflect distance.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Dijkstra;
+impl<G: CoordLookup> Heuristic<G> for Dijkstra {
+    fn estimate(&self, _grid: &G, _from: Index, _to: Index) -> usize {
+        0
+    }
+}
+
+/// Finds the shortest path from `start` to `end` following the links of the grid, visiting as
+/// few cells as the heuristic allows.  Returns `None` if `end` can not be reached.
+pub fn astar<'g, G, H>(grid: &'g G, start: Index, end: Index, heuristic: &H) -> Option<Path<'g, G>>
+where
+    G: CoordLookup,
+    H: Heuristic<G> + ?Sized,
+{
+    let mut steps: HashMap<Index, usize> = HashMap::new();
+    let mut came_from: HashMap<Index, Index> = HashMap::new();
+    let mut open = BinaryHeap::new();
+    steps.insert(start, 0);
+    open.push(Reverse((heuristic.estimate(grid, start, end), 0, start)));
+    while let Some(Reverse((_, taken, id))) = open.pop() {
+        // a shorter way to this cell was found after the entry was pushed
+        if taken > steps[&id] {
+            continue;
+        }
+        if id == end {
+            let mut path = vec![end];
+            let mut cur = end;
+            while let Some(prev) = came_from.get(&cur) {
+                path.push(*prev);
+                cur = *prev;
+            }
+            path.reverse();
+            return Some(Path::new(path, grid));
+        }
+        let next = taken + 1;
+        for link in grid.lookup(id).links().borrow().iter() {
+            if steps.get(link).is_none_or(|old| next < *old) {
+                steps.insert(*link, next);
+                came_from.insert(*link, id);
+                open.push(Reverse((
+                    next + heuristic.estimate(grid, *link, end),
+                    next,
+                    *link,
+                )));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::sq::SqGrid;
+    use crate::maze::{Grid, GridProps, Topology};
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn matches_distances() {
+        let mut rng = SplitMix64::seed_from_u64(31);
+        let grid = SqGrid::wilsons(12, &mut rng);
+        let last = grid.capacity().minus(1usize).into();
+        let expected = grid.shortest_path(0.into(), last);
+        let manhattan = grid.astar(0.into(), last, &Manhattan).unwrap();
+        let euclidean = grid.astar(0.into(), last, &Euclidean).unwrap();
+        assert_eq!(manhattan.get_ref(), expected.get_ref());
+        assert_eq!(euclidean.get_ref(), expected.get_ref());
+    }
+    #[test]
+    fn wrapping_and_unreachable() {
+        let mut rng = SplitMix64::seed_from_u64(31);
+        let grid = SqGrid::with_topology(8, Topology::Torus);
+        grid.carve_kruskals(&mut rng);
+        let expected = grid.shortest_path(9.into(), 54.into());
+        let path = grid.astar(9.into(), 54.into(), &Dijkstra).unwrap();
+        assert_eq!(path.len(), expected.len());
+        let blank = SqGrid::setup(4);
+        assert!(blank.astar(0.into(), 5.into(), &Manhattan).is_none());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::maze::{Cell, Grid, LinkStore};
+use crate::util::path::Path;
+use crate::util::Index;
+use std::collections::{HashMap, VecDeque};
+
+/// Cells reached by one side of a breadth first search, mapped to the cell they were reached
+/// from and their distance from the root.
+type Visited = HashMap<Index, (Index, usize)>;
+
+/// Follows the recorded steps from `id` back to the root of the search, including both ends.
+fn trace(visited: &Visited, id: Index) -> Vec<Index> {
+    let mut path = vec![id];
+    let mut cur = id;
+    while let Some((prev, _)) = visited.get(&cur).filter(|(prev, _)| *prev != cur) {
+        path.push(*prev);
+        cur = *prev;
+    }
+    path
+}
+
+/// Breadth first search that stops as soon as `end` is reached instead of flooding the whole
+/// grid like [`Grid::distances`].  Returns `None` if `end` can not be reached.
+pub fn bfs_path<G: Grid>(grid: &G, start: Index, end: Index) -> Option<Path<'_, G>> {
+    let mut visited = Visited::new();
+    let mut frontier = VecDeque::new();
+    visited.insert(start, (start, 0));
+    frontier.push_back(start);
+    while let Some(id) = frontier.pop_front() {
+        if id == end {
+            let mut path = trace(&visited, end);
+            path.reverse();
+            return Some(Path::new(path, grid));
+        }
+        let dist = visited[&id].1 + 1;
+        for link in grid.lookup(id).links().borrow().iter() {
+            if !visited.contains_key(link) {
+                visited.insert(*link, (id, dist));
+                frontier.push_back(*link);
+            }
+        }
+    }
+    None
+}
+
+/// Breadth first search that runs from both ends at once, one level at a time from whichever
+/// side has the smaller frontier, and stops when the two searches meet.  Explores far fewer cells
+/// than [`bfs_path`] on open grids.  Returns `None` if `end` can not be reached.
+pub fn bidirectional_path<G: Grid>(grid: &G, start: Index, end: Index) -> Option<Path<'_, G>> {
+    let mut forward = Visited::new();
+    let mut backward = Visited::new();
+    forward.insert(start, (start, 0));
+    backward.insert(end, (end, 0));
+    let mut frontiers = (vec![start], vec![end]);
+    let mut meet = if start == end { Some(start) } else { None };
+    while meet.is_none() {
+        if frontiers.0.is_empty() || frontiers.1.is_empty() {
+            return None;
+        }
+        let (frontier, seen, other) = match frontiers.0.len() <= frontiers.1.len() {
+            true => (&mut frontiers.0, &mut forward, &backward),
+            false => (&mut frontiers.1, &mut backward, &forward),
+        };
+        let mut next = Vec::new();
+        // finish the whole level so the shortest of the meeting points is picked
+        let mut best: Option<(usize, Index)> = None;
+        for id in frontier.iter() {
+            let dist = seen[id].1 + 1;
+            for link in grid.lookup(*id).links().borrow().iter() {
+                if seen.contains_key(link) {
+                    continue;
+                }
+                seen.insert(*link, (*id, dist));
+                next.push(*link);
+                if let Some((_, rest)) = other.get(link) {
+                    if best.is_none_or(|(total, _)| dist + rest < total) {
+                        best = Some((dist + rest, *link));
+                    }
+                }
+            }
+        }
+        *frontier = next;
+        meet = best.map(|(_, id)| id);
+    }
+    let meet = meet.unwrap();
+    let mut path = trace(&forward, meet);
+    path.reverse();
+    path.extend(trace(&backward, meet).into_iter().skip(1));
+    Some(Path::new(path, grid))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::sq::SqGrid;
+    use crate::maze::{Cell, Grid, GridProps};
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn matches_shortest_path() {
+        let mut rng = SplitMix64::seed_from_u64(32);
+        let grid = SqGrid::aldous_broder(10, &mut rng);
+        // a few extra links so there is more than one route
+        for (a, b) in [(11, 12), (45, 55), (67, 68), (20, 30)] {
+            grid.link(a.into(), b.into()).ok();
+        }
+        for (start, end) in [(0, 99), (5, 94), (42, 42), (9, 90)] {
+            let expected = grid.shortest_path(start.into(), end.into());
+            let bfs = grid.bfs_path(start.into(), end.into()).unwrap();
+            let both = grid.bidirectional_path(start.into(), end.into()).unwrap();
+            assert_eq!(bfs.len(), expected.len());
+            assert_eq!(both.len(), expected.len());
+            assert_eq!((both.first(), both.last()), (start.into(), end.into()));
+            for pair in both.get_ref().windows(2) {
+                assert!(grid.lookup(pair[0]).has_link(pair[1]));
+            }
+        }
+        let blank = SqGrid::setup(3);
+        assert!(blank.bfs_path(0.into(), 8.into()).is_none());
+        assert!(blank.bidirectional_path(0.into(), 8.into()).is_none());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::maze::{Cell, Grid};
+use crate::util::Index;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Tracks which set each cell belongs to while running [Kruskal's Algorithm][kruskals].
+///
+/// Cells start out in their own set.  Linking two cells merges their sets, so two cells in the
+/// same set are already connected and linking them again would create a loop.
+///
+/// [kruskals]: https://en.wikipedia.org/wiki/Maze_generation_algorithm#Randomized_Kruskal's_algorithm
+#[derive(Clone, Debug)]
+pub struct DisjointSets {
+    parent: Vec<usize>,
+}
+impl DisjointSets {
+    pub fn new(size: usize) -> Self {
+        Self {
+            parent: (0..size).collect(),
+        }
+    }
+    /// Adds a new cell in its own set, e.g. for cells created after the grid was set up.
+    pub fn push(&mut self) -> Index {
+        let id = self.parent.len();
+        self.parent.push(id);
+        id.into()
+    }
+    /// Finds the representative of the set containing `id`.
+    pub fn find(&mut self, id: Index) -> usize {
+        let mut root = *id;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut cur = *id;
+        while self.parent[cur] != root {
+            let next = self.parent[cur];
+            self.parent[cur] = root;
+            cur = next;
+        }
+        root
+    }
+    pub fn same(&mut self, a: Index, b: Index) -> bool {
+        self.find(a) == self.find(b)
+    }
+    pub fn merge(&mut self, a: Index, b: Index) {
+        let a = self.find(a);
+        let b = self.find(b);
+        self.parent[b] = a;
+    }
+}
+
+/// Links every pair of neighboring cells in random order, skipping pairs that are already in the
+/// same set.  Any links made beforehand must already be reflected in `sets`.
+pub(in crate) fn link_sets<G: Grid, R: Rng + ?Sized>(grid: &G, sets: &mut DisjointSets, rng: &mut R) {
+    let mut pairs: Vec<(Index, Index)> = Vec::new();
+    for cell in grid.cells() {
+        for n in cell.neighbor_ids().iter().filter(|n| **n > cell.id()) {
+            pairs.push((cell.id(), *n));
+        }
+    }
+    pairs.shuffle(rng);
+    for (a, b) in pairs {
+        if !sets.same(a, b) {
+            grid.link(a, b).unwrap();
+            sets.merge(a, b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn disjoint_sets_merge() {
+        let mut sets = DisjointSets::new(4);
+        sets.merge(0.into(), 1.into());
+        sets.merge(2.into(), 3.into());
+        assert!(sets.same(1.into(), 0.into()));
+        assert!(!sets.same(1.into(), 2.into()));
+        sets.merge(1.into(), 3.into());
+        assert!(sets.same(0.into(), 2.into()));
+        let id = sets.push();
+        assert!(!sets.same(id, 0.into()));
+    }
+}
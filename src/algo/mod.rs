@@ -1 +1,5 @@
-
+pub mod astar;
+pub mod bfs;
+pub mod kruskals;
+pub mod paths;
+pub mod solve;
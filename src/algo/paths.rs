@@ -0,0 +1,248 @@
+//! Tools for mazes with more than one solution, such as braided mazes.
+
+use crate::maze::{Cell, Grid, LinkStore};
+use crate::util::path::Path;
+use crate::util::Index;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Counts the simple paths (paths that never visit a cell twice) from `start` to `end`, stopping
+/// once `cap` paths have been found.  The number of paths grows very quickly with the number of
+/// loops, so a result equal to `cap` means there are at least that many.  A cell has a single
+/// path to itself.
+pub fn count_paths<G: Grid>(grid: &G, start: Index, end: Index, cap: usize) -> usize {
+    if start == end {
+        return 1;
+    }
+    let mut count = 0;
+    let mut visited: HashSet<Index> = HashSet::new();
+    // each entry is a cell on the current path and the links still left to try from it
+    let mut stack: Vec<(Index, Vec<Index>)> = vec![(start, links(grid, start))];
+    visited.insert(start);
+    while let Some((id, rest)) = stack.last_mut() {
+        if count >= cap {
+            break;
+        }
+        match rest.pop() {
+            Some(next) if next == end => count += 1,
+            Some(next) if !visited.contains(&next) => {
+                visited.insert(next);
+                stack.push((next, links(grid, next)));
+            }
+            Some(_) => {}
+            None => {
+                visited.remove(id);
+                stack.pop();
+            }
+        }
+    }
+    count
+}
+
+/// Finds up to `k` of the shortest simple paths from `start` to `end`, shortest first, using
+/// [Yen's algorithm][yen].
+///
+/// [yen]: https://en.wikipedia.org/wiki/Yen%27s_algorithm
+pub fn k_shortest_paths<G: Grid>(grid: &G, start: Index, end: Index, k: usize) -> Vec<Path<'_, G>> {
+    let mut found: Vec<Vec<Index>> = Vec::new();
+    let mut candidates: Vec<Vec<Index>> = Vec::new();
+    match search(grid, start, end, &HashSet::new(), &HashSet::new()) {
+        Some(path) if k > 0 => found.push(path),
+        _ => return Vec::new(),
+    }
+    while found.len() < k {
+        let prev = found.last().unwrap().clone();
+        for i in 0..prev.len() - 1 {
+            let root = &prev[..=i];
+            // passages already used by known paths sharing this root must not be taken again
+            let edges: HashSet<(Index, Index)> = found
+                .iter()
+                .filter(|p| p.len() > i + 1 && &p[..=i] == root)
+                .map(|p| (p[i], p[i + 1]))
+                .collect();
+            let nodes: HashSet<Index> = root[..i].iter().copied().collect();
+            if let Some(spur) = search(grid, prev[i], end, &nodes, &edges) {
+                let mut path = root[..i].to_vec();
+                path.extend(spur);
+                if !found.contains(&path) && !candidates.contains(&path) {
+                    candidates.push(path);
+                }
+            }
+        }
+        if candidates.is_empty() {
+            break;
+        }
+        // stable sort keeps the order candidates were found in for paths of the same length
+        candidates.sort_by_key(|p| p.len());
+        found.push(candidates.remove(0));
+    }
+    found.into_iter().map(|p| Path::new(p, grid)).collect()
+}
+
+/// Finds the bridges of the maze: passages whose removal would split the maze into two parts.
+/// In a perfect maze every passage is a bridge.  Each pair is ordered with the lower id first.
+pub fn bridges<G: Grid>(grid: &G) -> Vec<(Index, Index)> {
+    let size = *grid.capacity();
+    // order in which cells were discovered, and the earliest cell reachable from each subtree
+    let mut order: Vec<Option<usize>> = vec![None; size];
+    let mut low: Vec<usize> = vec![0; size];
+    let mut found = Vec::new();
+    let mut counter = 0;
+    for root in (0..size).map(Index::from) {
+        if order[*root].is_some() {
+            continue;
+        }
+        order[*root] = Some(counter);
+        low[*root] = counter;
+        counter += 1;
+        let mut stack: Vec<(Index, Option<Index>, Vec<Index>)> =
+            vec![(root, None, links(grid, root))];
+        while let Some((id, parent, rest)) = stack.last_mut() {
+            let (id, parent) = (*id, *parent);
+            match rest.pop() {
+                Some(next) if Some(next) == parent => {}
+                Some(next) => match order[*next] {
+                    Some(seen) => low[*id] = low[*id].min(seen),
+                    None => {
+                        order[*next] = Some(counter);
+                        low[*next] = counter;
+                        counter += 1;
+                        stack.push((next, Some(id), links(grid, next)));
+                    }
+                },
+                None => {
+                    stack.pop();
+                    if let Some(parent) = parent {
+                        low[*parent] = low[*parent].min(low[*id]);
+                        if low[*id] > order[*parent].unwrap() {
+                            found.push((parent.min(id), parent.max(id)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// The passages that every route from `start` to `end` has to use, in the order they are
+/// passed.  Returns an empty list if `end` can not be reached.
+pub fn required_passages<G: Grid>(grid: &G, start: Index, end: Index) -> Vec<(Index, Index)> {
+    let bridges: HashSet<(Index, Index)> = bridges(grid).into_iter().collect();
+    search(grid, start, end, &HashSet::new(), &HashSet::new())
+        .map(|path| {
+            path.windows(2)
+                .map(|w| (w[0], w[1]))
+                .filter(|(a, b)| bridges.contains(&(*a.min(b), *a.max(b))))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn links<G: Grid>(grid: &G, id: Index) -> Vec<Index> {
+    grid.lookup(id).links().borrow().to_vec()
+}
+
+/// Breadth first search that avoids the given cells and moving through the given passages in
+/// the given direction.
+fn search<G: Grid>(
+    grid: &G,
+    start: Index,
+    end: Index,
+    nodes: &HashSet<Index>,
+    edges: &HashSet<(Index, Index)>,
+) -> Option<Vec<Index>> {
+    let mut came_from: HashMap<Index, Index> = HashMap::new();
+    let mut frontier = VecDeque::from(vec![start]);
+    came_from.insert(start, start);
+    while let Some(id) = frontier.pop_front() {
+        if id == end {
+            let mut path = vec![end];
+            while *path.last().unwrap() != start {
+                path.push(came_from[path.last().unwrap()]);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in grid.lookup(id).links().borrow().iter() {
+            if nodes.contains(next) || edges.contains(&(id, *next)) || came_from.contains_key(next)
+            {
+                continue;
+            }
+            came_from.insert(*next, id);
+            frontier.push_back(*next);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::sq::SqGrid;
+    use crate::maze::GridProps;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    /// A 3x3 grid where the outer ring is open and the center links to 1:
+    /// two routes between opposite corners and a dead end in the middle.
+    fn ring() -> SqGrid {
+        let grid = SqGrid::setup(3);
+        for (a, b) in [
+            (0, 1),
+            (1, 2),
+            (2, 5),
+            (5, 8),
+            (8, 7),
+            (7, 6),
+            (6, 3),
+            (3, 0),
+            (1, 4),
+        ] {
+            grid.link(a.into(), b.into()).unwrap();
+        }
+        grid
+    }
+    #[test]
+    fn ring_paths() {
+        let grid = ring();
+        assert_eq!(count_paths(&grid, 0.into(), 8.into(), 100), 2);
+        assert_eq!(count_paths(&grid, 0.into(), 8.into(), 1), 1);
+        let paths = k_shortest_paths(&grid, 0.into(), 8.into(), 5);
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|p| p.len() == 5));
+        assert_eq!(bridges(&grid), vec![(1.into(), 4.into())]);
+        assert_eq!(
+            required_passages(&grid, 4.into(), 8.into()),
+            vec![(4.into(), 1.into())]
+        );
+        assert!(required_passages(&grid, 0.into(), 8.into()).is_empty());
+    }
+    #[test]
+    fn same_cell_paths() {
+        // the ring loops back to the start, which must not count as another path
+        let grid = ring();
+        assert_eq!(count_paths(&grid, 0.into(), 0.into(), 100), 1);
+        assert_eq!(count_paths(&grid, 4.into(), 4.into(), 100), 1);
+    }
+    #[test]
+    fn perfect_and_braided() {
+        let mut rng = SplitMix64::seed_from_u64(40);
+        let grid = SqGrid::wilsons(8, &mut rng);
+        let end: Index = 63.into();
+        assert_eq!(count_paths(&grid, 0.into(), end, 10), 1);
+        assert_eq!(bridges(&grid).len(), *grid.capacity() - 1);
+        let solution = grid.shortest_path(0.into(), end);
+        assert_eq!(
+            required_passages(&grid, 0.into(), end).len(),
+            solution.len() - 1
+        );
+        grid.braid(1.0, &mut rng);
+        assert!(count_paths(&grid, 0.into(), end, 1000) > 1);
+        let paths = k_shortest_paths(&grid, 0.into(), end, 4);
+        assert_eq!(paths[0].len(), grid.shortest_path(0.into(), end).len());
+        for pair in paths.windows(2) {
+            assert!(pair[0].len() <= pair[1].len());
+            assert_ne!(pair[0].get_ref(), pair[1].get_ref());
+        }
+    }
+}
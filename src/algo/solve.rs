@@ -0,0 +1,340 @@
+//! Maze solvers that mimic strategies a person could follow while walking through a maze,
+//! without knowing its layout in advance.
+
+use crate::maze::{CardinalGrid, Cell, Grid, LinkStore};
+use crate::util::path::Path;
+use crate::util::{Cardinal, Index};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The hand kept on the wall by [`wall_follower`] and [`pledge`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+impl Hand {
+    /// Turns from `dir` towards the hand.
+    pub fn turn(&self, dir: Cardinal) -> Cardinal {
+        match self {
+            Self::Left => dir.counter_clockwise(),
+            Self::Right => dir.clockwise(),
+        }
+    }
+    /// Turns from `dir` away from the hand.
+    pub fn turn_away(&self, dir: Cardinal) -> Cardinal {
+        match self {
+            Self::Left => dir.clockwise(),
+            Self::Right => dir.counter_clockwise(),
+        }
+    }
+}
+
+/// The outcome of a solver: every cell visited in order, including dead ends and backtracking,
+/// and the path from start to end that remains once the detours are removed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution<'g, G: Grid> {
+    route: Path<'g, G>,
+    path: Path<'g, G>,
+}
+impl<'g, G: Grid> Solution<'g, G> {
+    fn new(grid: &'g G, route: Vec<Index>) -> Self {
+        let path = erase_loops(&route);
+        Self {
+            route: Path::new(route, grid),
+            path: Path::new(path, grid),
+        }
+    }
+    /// All cells in the order the solver visited them.
+    pub fn route(&self) -> &Path<'g, G> {
+        &self.route
+    }
+    pub fn path(&self) -> &Path<'g, G> {
+        &self.path
+    }
+    /// Number of moves made by the solver.
+    pub fn steps(&self) -> usize {
+        self.route.len() - 1
+    }
+}
+
+/// Removes every detour that returns to a cell visited earlier.
+fn erase_loops(route: &[Index]) -> Vec<Index> {
+    let mut path: Vec<Index> = Vec::new();
+    for id in route {
+        match path.iter().position(|p| p == id) {
+            Some(pos) => path.truncate(pos + 1),
+            None => path.push(*id),
+        }
+    }
+    path
+}
+
+/// Walks through the maze keeping one hand on the wall, trying to turn towards that hand first,
+/// then going straight, then turning away and finally turning back.
+///
+/// Always succeeds in perfect mazes.  Returns `None` if the walk starts repeating itself without
+/// reaching `end`, which happens when `end` is not connected to the walls around `start`.
+pub fn wall_follower<G: CardinalGrid>(
+    grid: &G,
+    start: Index,
+    end: Index,
+    hand: Hand,
+) -> Option<Solution<'_, G>> {
+    let mut route = vec![start];
+    let mut cur = start;
+    let mut facing = Cardinal::N;
+    // each cell can be entered facing four directions before the walk must repeat
+    let max_steps = 4 * *grid.capacity();
+    while cur != end {
+        if route.len() > max_steps {
+            return None;
+        }
+        let mut dir = hand.turn(facing);
+        let next = (0..4).find_map(|_| {
+            let found = grid
+                .neighbor(cur, &dir)
+                .filter(|_| grid.has_dir_link(cur, &dir));
+            if found.is_none() {
+                dir = hand.turn_away(dir);
+            }
+            found
+        })?;
+        facing = dir;
+        cur = next;
+        route.push(cur);
+    }
+    Some(Solution::new(grid, route))
+}
+
+/// The Pledge algorithm: walks in the `preferred` direction until blocked, then follows the wall
+/// with `hand` while counting turns, and leaves the wall once the turns add up to zero.
+///
+/// Unlike [`wall_follower`] it can escape from walls that are not connected to the outside, but
+/// it is only guaranteed to reach cells on the boundary of the grid.  Returns `None` if `end` is
+/// not reached within a number of steps proportional to the size of the grid.
+pub fn pledge<G: CardinalGrid>(
+    grid: &G,
+    start: Index,
+    end: Index,
+    preferred: Cardinal,
+    hand: Hand,
+) -> Option<Solution<'_, G>> {
+    let open = |id: Index, dir: &Cardinal| {
+        grid.neighbor(id, dir)
+            .filter(|_| grid.has_dir_link(id, dir))
+    };
+    // turning towards the hand counts as +1, turning away as -1
+    let delta = |turns: usize| -> i32 {
+        match turns {
+            0 => 1,
+            1 => 0,
+            n => 1 - n as i32,
+        }
+    };
+    let mut route = vec![start];
+    let mut cur = start;
+    let mut facing = preferred;
+    let mut turns = 0;
+    let max_steps = 16 * *grid.capacity();
+    while cur != end {
+        if route.len() > max_steps {
+            return None;
+        }
+        if turns == 0 {
+            if let Some(next) = open(cur, &facing) {
+                cur = next;
+                route.push(cur);
+                continue;
+            }
+            // blocked: turn away from the hand until the wall is on the side of the hand
+            let mut dir = facing;
+            let mut n = 0;
+            while open(cur, &dir).is_none() && n > -4 {
+                dir = hand.turn_away(dir);
+                n -= 1;
+            }
+            if n == -4 {
+                return None;
+            }
+            facing = dir;
+            turns = n;
+        } else {
+            let mut dir = hand.turn(facing);
+            let tried = (0..4).find(|_| {
+                let found = open(cur, &dir).is_some();
+                if !found {
+                    dir = hand.turn_away(dir);
+                }
+                found
+            })?;
+            facing = dir;
+            turns += delta(tried);
+        }
+        cur = open(cur, &facing).unwrap();
+        route.push(cur);
+    }
+    Some(Solution::new(grid, route))
+}
+
+/// Trémaux's algorithm: marks each passage every time it is walked through.  New junctions are
+/// left through an unmarked passage, junctions that were already visited are left the way they
+/// were entered, and no passage is walked more than twice.
+///
+/// Works on any maze, including mazes with loops.  Returns `None` if `end` can not be reached.
+pub fn tremaux<G: Grid>(grid: &G, start: Index, end: Index) -> Option<Solution<'_, G>> {
+    let key = |a: Index, b: Index| (a.min(b), a.max(b));
+    let mut marks: HashMap<(Index, Index), u8> = HashMap::new();
+    let mut visits: HashMap<Index, usize> = HashMap::new();
+    let mut route = vec![start];
+    let mut prev: Option<Index> = None;
+    let mut cur = start;
+    while cur != end {
+        let count = visits.entry(cur).or_insert(0);
+        *count += 1;
+        let seen = *count > 1;
+        let links = grid.lookup(cur).links().borrow().to_vec();
+        let marked = |n: &Index| marks.get(&key(cur, *n)).copied().unwrap_or(0);
+        let next = match prev {
+            Some(p) if seen && marked(&p) == 1 => Some(p),
+            _ => links
+                .iter()
+                .filter(|n| marked(n) < 2)
+                .min_by_key(|n| marked(n))
+                .copied(),
+        }?;
+        *marks.entry(key(cur, next)).or_insert(0) += 1;
+        prev = Some(cur);
+        cur = next;
+        route.push(cur);
+    }
+    Some(Solution::new(grid, route))
+}
+
+/// Dead-end filling: repeatedly fills cells that only have one open passage until only the
+/// passages between `start` and `end` are left.  This is not done by walking, so the route lists
+/// the filled cells in the order they were filled, followed by the path.
+///
+/// In a perfect maze the remaining cells form the only path.  Returns `None` if `end` can not be
+/// reached.
+pub fn dead_end_filling<G: Grid>(grid: &G, start: Index, end: Index) -> Option<Solution<'_, G>> {
+    let mut filled: HashSet<Index> = HashSet::new();
+    let open_links = |id: Index, filled: &HashSet<Index>| {
+        grid.lookup(id)
+            .links()
+            .borrow()
+            .iter()
+            .filter(|n| !filled.contains(n))
+            .count()
+    };
+    let mut queue: VecDeque<Index> = grid
+        .cells()
+        .iter()
+        .map(|c| c.id())
+        .filter(|id| *id != start && *id != end && open_links(*id, &filled) <= 1)
+        .collect();
+    let mut order = Vec::new();
+    while let Some(id) = queue.pop_front() {
+        if filled.contains(&id) {
+            continue;
+        }
+        filled.insert(id);
+        order.push(id);
+        for n in grid.lookup(id).links().borrow().iter() {
+            if *n != start && *n != end && !filled.contains(n) && open_links(*n, &filled) <= 1 {
+                queue.push_back(*n);
+            }
+        }
+    }
+    // the remaining cells may still contain loops, so search them for the shortest path
+    let mut came_from: HashMap<Index, Index> = HashMap::new();
+    let mut frontier = VecDeque::from(vec![start]);
+    came_from.insert(start, start);
+    while let Some(id) = frontier.pop_front() {
+        if id == end {
+            break;
+        }
+        for n in grid.lookup(id).links().borrow().iter() {
+            if !filled.contains(n) && !came_from.contains_key(n) {
+                came_from.insert(*n, id);
+                frontier.push_back(*n);
+            }
+        }
+    }
+    came_from.get(&end)?;
+    let mut path = vec![end];
+    while *path.last().unwrap() != start {
+        path.push(came_from[path.last().unwrap()]);
+    }
+    path.reverse();
+    let mut route = order;
+    route.extend(path.iter().copied());
+    Some(Solution {
+        route: Path::new(route, grid),
+        path: Path::new(path, grid),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::sq::SqGrid;
+    use crate::maze::GridProps;
+    use crate::render::Renderer;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn solvers_find_shortest_path() -> Result<(), image::ImageError> {
+        let mut rng = SplitMix64::seed_from_u64(33);
+        let grid = SqGrid::wilsons(8, &mut rng);
+        let end = grid.capacity().minus(1usize).into();
+        let expected = grid.shortest_path(0.into(), end);
+        let solutions = [
+            wall_follower(&grid, 0.into(), end, Hand::Left).unwrap(),
+            wall_follower(&grid, 0.into(), end, Hand::Right).unwrap(),
+            pledge(&grid, 0.into(), end, Cardinal::E, Hand::Left).unwrap(),
+            tremaux(&grid, 0.into(), end).unwrap(),
+            dead_end_filling(&grid, 0.into(), end).unwrap(),
+        ];
+        for solution in solutions.iter() {
+            assert_eq!(solution.path().get_ref(), expected.get_ref());
+            assert!(solution.steps() + 1 >= expected.len());
+        }
+        solutions[3]
+            .route()
+            .render_defaults()
+            .save_render(std::path::Path::new("tremaux.png"))
+    }
+    #[test]
+    fn loops_and_islands() {
+        // a ring of passages around cell 4, which is only reachable from cell 1
+        let grid = SqGrid::setup(3);
+        for (a, b) in [
+            (0, 1),
+            (1, 2),
+            (2, 5),
+            (5, 8),
+            (8, 7),
+            (7, 6),
+            (6, 3),
+            (3, 0),
+        ] {
+            grid.link(a.into(), b.into()).unwrap();
+        }
+        grid.link(4.into(), 1.into()).unwrap();
+        // the left hand stays on the outer wall and circles the ring forever
+        assert!(wall_follower(&grid, 0.into(), 4.into(), Hand::Left).is_none());
+        assert!(wall_follower(&grid, 0.into(), 4.into(), Hand::Right).is_some());
+        let solution = tremaux(&grid, 4.into(), 8.into()).unwrap();
+        assert_eq!(
+            (solution.path().first(), solution.path().last()),
+            (4.into(), 8.into())
+        );
+        let filled = dead_end_filling(&grid, 0.into(), 8.into()).unwrap();
+        assert_eq!(filled.path().len(), 5);
+        assert!(tremaux(&SqGrid::setup(2), 0.into(), 3.into()).is_none());
+        assert_eq!(
+            erase_loops(&[0.into(), 1.into(), 2.into(), 1.into(), 3.into()]).len(),
+            3
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Statistics describing the texture of a maze, useful for comparing generators.
+
+pub mod difficulty;
+
+use crate::algo::kruskals::DisjointSets;
+use crate::maze::{CardinalGrid, Cell, Grid, LinkStore};
+use crate::util::{Index, Ordinal};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Measurements of a maze, see [`Metrics::new`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    /// Number of cells in the grid.
+    pub cells: usize,
+    /// Cells with a single passage.
+    pub dead_ends: usize,
+    /// Cells with three or more passages.
+    pub junctions: usize,
+    /// Cells with two passages on opposite sides.
+    pub corridors: usize,
+    /// Cells with two passages that are not on opposite sides.
+    pub turns: usize,
+    /// Average number of steps between two cells that are not part of a corridor or a turn,
+    /// i.e. the length of the passages between junctions and dead ends.
+    pub average_passage_length: f64,
+    /// Steps on the shortest path from start to end, `None` if there is no such path.
+    pub solution_length: Option<usize>,
+    /// Steps on the longest path in the maze, found by [`Grid::longest_path`].
+    pub diameter: usize,
+}
+impl Metrics {
+    /// Measures the maze, taking the solution to run from the northwest to the southeast corner.
+    pub fn new<G: CardinalGrid>(grid: &G) -> Self {
+        Self::between(
+            grid,
+            grid.corner_id(Ordinal::Nw),
+            grid.corner_id(Ordinal::Se),
+        )
+    }
+    /// Measures the maze, taking the solution to run from `start` to `end`.
+    pub fn between<G: CardinalGrid>(grid: &G, start: Index, end: Index) -> Self {
+        let cells = *grid.capacity();
+        let (mut dead_ends, mut junctions, mut corridors, mut turns) = (0, 0, 0, 0);
+        for cell in grid.cells() {
+            let links = cell.links().borrow();
+            match links.len() {
+                1 => dead_ends += 1,
+                2 => {
+                    let dirs = (
+                        grid.dir_from(cell.id(), links[0]),
+                        grid.dir_from(cell.id(), links[1]),
+                    );
+                    match dirs {
+                        (Some(a), Some(b)) if a.clockwise().clockwise() == b => corridors += 1,
+                        _ => turns += 1,
+                    }
+                }
+                0 => {}
+                _ => junctions += 1,
+            }
+        }
+        Self {
+            cells,
+            dead_ends,
+            junctions,
+            corridors,
+            turns,
+            average_passage_length: average_passage_length(grid),
+            solution_length: grid.bfs_path(start, end).map(|p| p.len() - 1),
+            diameter: grid.longest_path(Index::zero()).len() - 1,
+        }
+    }
+    pub fn dead_end_ratio(&self) -> f64 {
+        self.dead_ends as f64 / self.cells as f64
+    }
+    /// Share of cells that only continue a passage without branching.  Mazes with long, winding
+    /// passages ("rivers") score close to `1.0`, mazes with many short dead ends score low.
+    pub fn river_factor(&self) -> f64 {
+        (self.corridors + self.turns) as f64 / self.cells as f64
+    }
+    /// Length of the solution compared to the number of cells.
+    pub fn relative_solution_length(&self) -> Option<f64> {
+        self.solution_length.map(|l| l as f64 / self.cells as f64)
+    }
+}
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "cells: {}", self.cells)?;
+        writeln!(
+            f,
+            "dead ends: {} ({:.3})",
+            self.dead_ends,
+            self.dead_end_ratio()
+        )?;
+        writeln!(f, "junctions: {}", self.junctions)?;
+        writeln!(f, "corridors: {}", self.corridors)?;
+        writeln!(f, "turns: {}", self.turns)?;
+        writeln!(
+            f,
+            "average passage length: {:.3}",
+            self.average_passage_length
+        )?;
+        writeln!(f, "river factor: {:.3}", self.river_factor())?;
+        match (self.solution_length, self.relative_solution_length()) {
+            (Some(len), Some(rel)) => writeln!(f, "solution length: {} ({:.3})", len, rel)?,
+            _ => writeln!(f, "solution length: none")?,
+        }
+        write!(f, "diameter: {}", self.diameter)
+    }
+}
+
+/// Structural problems found in a maze, see [`Grid::validate`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Validation {
+    /// Number of groups of cells that are connected to each other.
+    pub components: usize,
+    /// Cells without any links.
+    pub isolated: Vec<Index>,
+    /// Links that close a loop, one for each loop in the maze.  Removing them all leaves a
+    /// spanning forest.
+    pub loops: Vec<(Index, Index)>,
+    /// Pairs `(a, b)` where `a` links to `b` but `b` does not link back to `a`.
+    pub asymmetric: Vec<(Index, Index)>,
+}
+impl Validation {
+    pub fn new<G: Grid>(grid: &G) -> Self {
+        let mut report = Self::default();
+        let mut sets = DisjointSets::new(*grid.capacity());
+        let mut seen: HashSet<(Index, Index)> = HashSet::new();
+        for cell in grid.cells() {
+            let a = cell.id();
+            let links = cell.links().borrow();
+            if links.is_empty() {
+                report.isolated.push(a);
+            }
+            for b in links.iter().copied() {
+                if !grid.lookup(b).has_link(a) {
+                    report.asymmetric.push((a, b));
+                }
+                if !seen.insert((a.min(b), a.max(b))) {
+                    continue;
+                }
+                if sets.same(a, b) {
+                    report.loops.push((a, b));
+                } else {
+                    sets.merge(a, b);
+                }
+            }
+        }
+        let roots: HashSet<usize> = grid.cells().iter().map(|c| sets.find(c.id())).collect();
+        report.components = roots.len();
+        report
+    }
+    /// Whether every cell can be reached from every other cell.
+    pub fn is_connected(&self) -> bool {
+        self.components == 1
+    }
+    /// Whether the maze is a spanning tree: connected, without loops and with consistent links,
+    /// so there is exactly one path between any two cells.
+    pub fn is_perfect(&self) -> bool {
+        self.is_connected() && self.loops.is_empty() && self.asymmetric.is_empty()
+    }
+}
+
+/// Walks every passage from each cell that is not part of one, counting the steps until the next
+/// such cell.  Every passage is walked from both ends, which does not change the average.
+fn average_passage_length<G: Grid>(grid: &G) -> f64 {
+    let degree = |id: Index| grid.lookup(id).links().borrow().len();
+    let (mut total, mut count) = (0usize, 0usize);
+    for cell in grid
+        .cells()
+        .iter()
+        .filter(|c| c.links().borrow().len() != 2)
+    {
+        for first in cell.links().borrow().iter() {
+            let (mut prev, mut cur, mut steps) = (cell.id(), *first, 1);
+            while degree(cur) == 2 {
+                let next = grid
+                    .lookup(cur)
+                    .links()
+                    .borrow()
+                    .iter()
+                    .copied()
+                    .find(|n| *n != prev)
+                    .unwrap();
+                prev = cur;
+                cur = next;
+                steps += 1;
+            }
+            total += steps;
+            count += 1;
+        }
+    }
+    match count {
+        0 => 0.0,
+        _ => total as f64 / count as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::sq::SqGrid;
+    use crate::maze::GridProps;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn counts_cells() {
+        // a single corridor bent into an L: 0 - 1 - 2 - 5 - 8
+        let grid = SqGrid::setup(3);
+        for (a, b) in [(0, 1), (1, 2), (2, 5), (5, 8)] {
+            grid.link(a.into(), b.into()).unwrap();
+        }
+        let metrics = Metrics::new(&grid);
+        assert_eq!(metrics.dead_ends, 2);
+        assert_eq!(
+            (metrics.corridors, metrics.turns, metrics.junctions),
+            (2, 1, 0)
+        );
+        assert_eq!(metrics.average_passage_length, 4.0);
+        assert_eq!(metrics.solution_length, Some(4));
+        assert_eq!(metrics.diameter, 4);
+    }
+    #[test]
+    fn validate() {
+        let mut rng = SplitMix64::seed_from_u64(36);
+        let grid = SqGrid::kruskals(6, &mut rng);
+        let report = grid.validate();
+        assert!(report.is_perfect());
+        assert!(report.isolated.is_empty());
+        let blank = SqGrid::setup(3);
+        for (a, b) in [(0, 1), (1, 4), (4, 3), (3, 0), (7, 8)] {
+            blank.link(a.into(), b.into()).unwrap();
+        }
+        blank.lookup(2.into()).unchecked_link(5.into());
+        let report = blank.validate();
+        assert_eq!(report.components, 4);
+        assert_eq!(report.isolated, vec![5.into(), 6.into()]);
+        assert_eq!(report.loops.len(), 1);
+        assert_eq!(report.asymmetric, vec![(2.into(), 5.into())]);
+        assert!(!report.is_connected());
+    }
+    #[test]
+    fn compare_generators() {
+        let mut rng = SplitMix64::seed_from_u64(35);
+        let tree = SqGrid::binary_tree(12, &mut rng);
+        let wilsons = SqGrid::wilsons(12, &mut rng);
+        for grid in [&tree, &wilsons] {
+            let metrics = Metrics::new(grid);
+            let cells = metrics.dead_ends + metrics.junctions + metrics.corridors + metrics.turns;
+            assert_eq!(cells, metrics.cells);
+            assert!(metrics.diameter >= metrics.solution_length.unwrap());
+            assert!(!metrics.to_string().is_empty());
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::maze::{Cell, Grid, LinkStore};
+use crate::util::path::Path;
+use crate::util::Index;
+use rand::Rng;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// How hard it is to get from one cell to another, see [`Difficulty::new`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difficulty {
+    pub start: Index,
+    pub end: Index,
+    /// Steps on the shortest path.
+    pub path_length: usize,
+    /// Cells on the path with more than one way to continue.
+    pub decision_points: usize,
+    /// Wrong branches leaving the path.
+    pub branches: usize,
+    /// Sum of how far each wrong branch reaches before it ends.
+    pub branch_depth: usize,
+}
+impl Difficulty {
+    /// Measures the shortest path between `start` and `end`.  Returns `None` if there is none.
+    pub fn new<G: Grid>(grid: &G, start: Index, end: Index) -> Option<Self> {
+        let path = grid.bfs_path(start, end)?;
+        Some(Self::from_path(&path))
+    }
+    /// Measures an existing path, which is expected to be the shortest path between its ends.
+    pub fn from_path<G: Grid>(path: &Path<'_, G>) -> Self {
+        let grid = path.grid();
+        let on_path: HashSet<Index> = path.iter().copied().collect();
+        let (mut decision_points, mut branches, mut branch_depth) = (0, 0, 0);
+        for id in path.iter().take(path.len().saturating_sub(1)) {
+            let wrong: Vec<Index> = grid
+                .lookup(*id)
+                .links()
+                .borrow()
+                .iter()
+                .copied()
+                .filter(|n| !on_path.contains(n))
+                .collect();
+            if !wrong.is_empty() {
+                decision_points += 1;
+            }
+            for n in wrong {
+                branches += 1;
+                branch_depth += depth(grid, n, &on_path);
+            }
+        }
+        Self {
+            start: path.first(),
+            end: path.last(),
+            path_length: path.len() - 1,
+            decision_points,
+            branches,
+            branch_depth,
+        }
+    }
+    /// A rough estimate of the steps needed by someone who does not know the maze: the path
+    /// itself, plus every wrong branch explored to its end.
+    pub fn score(&self) -> f64 {
+        (self.path_length + self.decision_points + self.branch_depth) as f64
+    }
+    /// Tries up to `attempts` random pairs of distinct cells and returns the first pair whose
+    /// score falls within `band`.  Pairs drawing the same cell twice are skipped, so a grid with a
+    /// single cell never has any entrances.
+    pub fn pick_entrances<G: Grid, R: Rng + ?Sized>(
+        grid: &G,
+        band: &Range<f64>,
+        attempts: usize,
+        rng: &mut R,
+    ) -> Option<Self> {
+        (0..attempts).find_map(|_| {
+            let (start, end) = (grid.random_id(rng), grid.random_id(rng));
+            if start == end {
+                return None;
+            }
+            Self::new(grid, start, end).filter(|d| band.contains(&d.score()))
+        })
+    }
+}
+
+/// Generates mazes with `generate` until one has a pair of cells within the difficulty `band`,
+/// trying `attempts` pairs of cells per maze and at most `mazes` mazes.
+///
+/// ```
+/// use mazes::analysis::difficulty::generate_with_difficulty;
+/// use mazes::{Grid, SqGrid};
+/// # use rand::SeedableRng;
+/// # let mut rng = rand_xoshiro::SplitMix64::seed_from_u64(1);
+/// let (grid, difficulty) =
+///     generate_with_difficulty(|rng| SqGrid::wilsons(10, rng), 40.0..60.0, 10, 20, &mut rng)
+///         .unwrap();
+/// assert!(difficulty.score() >= 40.0);
+/// ```
+pub fn generate_with_difficulty<G, R, F>(
+    mut generate: F,
+    band: Range<f64>,
+    mazes: usize,
+    attempts: usize,
+    rng: &mut R,
+) -> Option<(G, Difficulty)>
+where
+    G: Grid,
+    R: Rng + ?Sized,
+    F: FnMut(&mut R) -> G,
+{
+    for _ in 0..mazes {
+        let grid = generate(rng);
+        if let Some(difficulty) = Difficulty::pick_entrances(&grid, &band, attempts, rng) {
+            return Some((grid, difficulty));
+        }
+    }
+    None
+}
+
+/// How many steps the branch starting at `root` reaches without returning to the path.
+fn depth<G: Grid>(grid: &G, root: Index, on_path: &HashSet<Index>) -> usize {
+    let mut seen: HashSet<Index> = HashSet::new();
+    seen.insert(root);
+    let mut frontier = vec![root];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = Vec::new();
+        for id in frontier {
+            for link in grid.lookup(id).links().borrow().iter() {
+                if !on_path.contains(link) && seen.insert(*link) {
+                    next.push(*link);
+                }
+            }
+        }
+        frontier = next;
+    }
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::sq::SqGrid;
+    use crate::maze::GridProps;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn score_branches() {
+        // path 0 - 1 - 2 with a wrong branch 1 - 4 - 7 - 8
+        let grid = SqGrid::setup(3);
+        for (a, b) in [(0, 1), (1, 2), (1, 4), (4, 7), (7, 8)] {
+            grid.link(a.into(), b.into()).unwrap();
+        }
+        let difficulty = Difficulty::new(&grid, 0.into(), 2.into()).unwrap();
+        assert_eq!(difficulty.path_length, 2);
+        assert_eq!(difficulty.decision_points, 1);
+        assert_eq!(difficulty.branches, 1);
+        assert_eq!(difficulty.branch_depth, 3);
+        assert_eq!(difficulty.score(), 6.0);
+        assert!(Difficulty::new(&grid, 0.into(), 3.into()).is_none());
+    }
+    #[test]
+    fn target_band() {
+        let mut rng = SplitMix64::seed_from_u64(38);
+        let band = 30.0..50.0;
+        let (grid, difficulty) =
+            generate_with_difficulty(|r| SqGrid::wilsons(8, r), band.clone(), 5, 50, &mut rng)
+                .unwrap();
+        assert!(band.contains(&difficulty.score()));
+        let again = Difficulty::new(&grid, difficulty.start, difficulty.end).unwrap();
+        assert_eq!(again, difficulty);
+    }
+    #[test]
+    fn distinct_entrances() {
+        let mut rng = SplitMix64::seed_from_u64(38);
+        // only a path from a cell to itself scores 0
+        let single = SqGrid::setup(1);
+        assert!(Difficulty::pick_entrances(&single, &(-1.0..1.0), 50, &mut rng).is_none());
+        let grid = SqGrid::wilsons(2, &mut rng);
+        assert!(Difficulty::pick_entrances(&grid, &(-1.0..1.0), 50, &mut rng).is_none());
+        let picked = Difficulty::pick_entrances(&grid, &(0.0..100.0), 50, &mut rng).unwrap();
+        assert_ne!(picked.start, picked.end);
+    }
+}
@@ -46,3 +46,61 @@ impl<'s, T: std::fmt::Display> NotNeighborsError<'s, T> {
         Self { a, t, reason }
     }
 }
+
+#[derive(Error, Debug)]
+#[error("Cell {0} has no boundary to the {1}")]
+pub struct NoBoundaryError(Index, Cardinal);
+impl NoBoundaryError {
+    pub(in crate) fn new(id: Index, dir: Cardinal) -> Self {
+        Self(id, dir)
+    }
+}
+
+/// Errors reading or writing the binary maze format in [`crate::io::binary`], or deserializing
+/// a grid with the `serde` feature.
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error("Data does not start with the maze file signature")]
+    BadMagic,
+    #[error("Unsupported format version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Unknown topology {0}")]
+    UnknownTopology(u8),
+    #[error("Only square grids are supported, found {rows}x{cols}")]
+    NotSquare { rows: usize, cols: usize },
+    #[error("A grid of {rows}x{cols} is empty, too large to read or too small for its topology")]
+    InvalidSize { rows: usize, cols: usize },
+    #[error("Generator name must be valid UTF-8 of at most 255 bytes")]
+    InvalidName,
+    #[error("Cell {0} links to a cell that is not its neighbor or does not link back")]
+    InvalidLink(Index),
+    #[error("Cell {0} has an opening on a side that is not on the boundary")]
+    InvalidOpening(Index),
+    #[error("Cell {0} does not match its position in the grid")]
+    InvalidCell(Index),
+    #[error("Expected {expected} cells, found {found}")]
+    CellCount { expected: usize, found: usize },
+    #[error("Data ended before the whole maze was read")]
+    Truncated,
+    #[error(transparent)]
+    Io(std::io::Error),
+}
+impl From<std::io::Error> for FormatError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => Self::Truncated,
+            _ => Self::Io(e),
+        }
+    }
+}
+
+/// Errors reconstructing a maze from a rendered image in [`crate::io::png`].
+#[derive(Error, Debug)]
+pub enum ImageImportError {
+    #[error("Image of {width}x{height} does not fit a square grid with the given options")]
+    Dimensions { width: u32, height: u32 },
+    #[error("Walls can not be found in images rendered with a border width of 0")]
+    NoBorders,
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+}
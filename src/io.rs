@@ -0,0 +1,7 @@
+//! Reading and writing mazes in other formats.
+
+pub mod binary;
+pub mod graph;
+pub mod mesh;
+pub mod png;
+pub mod tiled;
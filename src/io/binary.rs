@@ -0,0 +1,294 @@
+//! A compact binary encoding for [`SqGrid`] mazes.
+//!
+//! Every link between two cells is either to the east or to the south of one of them, so the
+//! maze is stored as two bits per cell: bit 0 is set when the cell links to its east neighbor
+//! and bit 1 when it links to its south neighbor.  Four cells are packed into each byte, lowest
+//! bits first, following a header:
+//!
+//! | bytes | content                                        |
+//! |-------|------------------------------------------------|
+//! | 4     | magic bytes `MAZE`                             |
+//! | 1     | format version, currently `1`                  |
+//! | 1     | topology: `0` plane, `1` cylinder, `2` torus   |
+//! | 4     | rows, little endian                            |
+//! | 4     | columns, little endian                         |
+//! | 1     | length of the generator name                   |
+//! | n     | generator name, UTF-8                          |
+//! | 1     | `1` if an RNG seed follows, `0` otherwise      |
+//! | 0 / 8 | RNG seed, little endian                        |
+//!
+//! The links are followed by the openings in the outer wall (see [`SqGrid::open_boundary`]), as
+//! a little endian `u32` count and five bytes for each opening: the cell id as a little endian
+//! `u32` and the side, `0` to `3` for north, east, south and west.
+//!
+//! Grids of more than [`MAX_CELLS`] cells are rejected when writing, and when reading before
+//! anything is allocated for them, so every file that is written can be read back.
+
+use crate::error::FormatError;
+use crate::maze::sq::SqGrid;
+use crate::maze::{CardinalGrid, Grid, GridProps, Topology};
+use crate::util::{Cardinal, Index};
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+pub const MAGIC: [u8; 4] = *b"MAZE";
+pub const VERSION: u8 = 1;
+/// The largest grid [`read`] and [`write`] accept, a 4096×4096 maze.
+pub const MAX_CELLS: usize = 1 << 24;
+
+/// Information about how a maze was made, stored in the header next to the links.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// Name of the algorithm that generated the maze, at most 255 bytes.
+    pub generator: String,
+    /// Seed of the random number generator, to reproduce the maze.
+    pub seed: Option<u64>,
+}
+impl Metadata {
+    pub fn new(generator: &str, seed: Option<u64>) -> Self {
+        Self {
+            generator: generator.to_string(),
+            seed,
+        }
+    }
+}
+
+pub fn write<W: Write>(grid: &SqGrid, meta: &Metadata, mut w: W) -> Result<(), FormatError> {
+    let topology = match grid.topology() {
+        Topology::Plane => 0u8,
+        Topology::Cylinder => 1,
+        Topology::Torus => 2,
+    };
+    let name = meta.generator.as_bytes();
+    let name_len = u8::try_from(name.len()).map_err(|_| FormatError::InvalidName)?;
+    let (rows, cols) = grid.dimensions();
+    check_size(*rows, *cols)?;
+    w.write_all(&MAGIC)?;
+    w.write_all(&[VERSION, topology])?;
+    w.write_all(&(*rows as u32).to_le_bytes())?;
+    w.write_all(&(*cols as u32).to_le_bytes())?;
+    w.write_all(&[name_len])?;
+    w.write_all(name)?;
+    match meta.seed {
+        Some(seed) => {
+            w.write_all(&[1])?;
+            w.write_all(&seed.to_le_bytes())?;
+        }
+        None => w.write_all(&[0])?,
+    }
+    let mut links = vec![0u8; grid.capacity().div_ceil(4)];
+    for id in (0..*grid.capacity()).map(Index::from) {
+        let bits = u8::from(grid.has_dir_link(id, &Cardinal::E))
+            | u8::from(grid.has_dir_link(id, &Cardinal::S)) << 1;
+        links[*id / 4] |= bits << ((*id % 4) * 2);
+    }
+    w.write_all(&links)?;
+    let openings = grid.openings();
+    w.write_all(&(openings.len() as u32).to_le_bytes())?;
+    for (id, dir) in openings {
+        let side = match dir {
+            Cardinal::N => 0u8,
+            Cardinal::E => 1,
+            Cardinal::S => 2,
+            Cardinal::W => 3,
+        };
+        w.write_all(&(**id as u32).to_le_bytes())?;
+        w.write_all(&[side])?;
+    }
+    Ok(())
+}
+
+pub fn read<R: Read>(mut r: R) -> Result<(SqGrid, Metadata), FormatError> {
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(FormatError::BadMagic);
+    }
+    let [version, topology] = read_array(&mut r)?;
+    if version != VERSION {
+        return Err(FormatError::UnsupportedVersion(version));
+    }
+    let topology = match topology {
+        0 => Topology::Plane,
+        1 => Topology::Cylinder,
+        2 => Topology::Torus,
+        t => return Err(FormatError::UnknownTopology(t)),
+    };
+    let rows = u32::from_le_bytes(read_array(&mut r)?) as usize;
+    let cols = u32::from_le_bytes(read_array(&mut r)?) as usize;
+    if rows != cols {
+        return Err(FormatError::NotSquare { rows, cols });
+    }
+    let capacity = check_size(rows, cols)?;
+    if !topology.fits(rows) {
+        return Err(FormatError::InvalidSize { rows, cols });
+    }
+    let [name_len] = read_array(&mut r)?;
+    let mut name = vec![0u8; name_len as usize];
+    r.read_exact(&mut name)?;
+    let generator = String::from_utf8(name).map_err(|_| FormatError::InvalidName)?;
+    let seed = match read_array(&mut r)? {
+        [0] => None,
+        _ => Some(u64::from_le_bytes(read_array(&mut r)?)),
+    };
+    let mut links = vec![0u8; capacity.div_ceil(4)];
+    r.read_exact(&mut links)?;
+    let mut grid = SqGrid::with_topology(rows, topology);
+    for id in (0..capacity).map(Index::from) {
+        let bits = links[*id / 4] >> ((*id % 4) * 2);
+        for (bit, dir) in [(1, Cardinal::E), (2, Cardinal::S)] {
+            if bits & bit != 0 {
+                let n = grid
+                    .neighbor(id, &dir)
+                    .ok_or(FormatError::InvalidLink(id))?;
+                grid.link(id, n).map_err(|_| FormatError::InvalidLink(id))?;
+            }
+        }
+    }
+    let count = u32::from_le_bytes(read_array(&mut r)?);
+    for _ in 0..count {
+        let id = Index::from(u32::from_le_bytes(read_array(&mut r)?) as usize);
+        let dir = match read_array(&mut r)? {
+            [0] => Cardinal::N,
+            [1] => Cardinal::E,
+            [2] => Cardinal::S,
+            [3] => Cardinal::W,
+            _ => return Err(FormatError::InvalidOpening(id)),
+        };
+        grid.open_boundary(id, dir)
+            .map_err(|_| FormatError::InvalidOpening(id))?;
+    }
+    Ok((grid, Metadata { generator, seed }))
+}
+
+pub fn to_bytes(grid: &SqGrid, meta: &Metadata) -> Result<Vec<u8>, FormatError> {
+    let mut bytes = Vec::new();
+    write(grid, meta, &mut bytes)?;
+    Ok(bytes)
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<(SqGrid, Metadata), FormatError> {
+    read(bytes)
+}
+
+/// The number of cells in a `rows`×`cols` grid, if it has at least one and at most [`MAX_CELLS`].
+fn check_size(rows: usize, cols: usize) -> Result<usize, FormatError> {
+    rows.checked_mul(cols)
+        .filter(|c| (1..=MAX_CELLS).contains(c))
+        .ok_or(FormatError::InvalidSize { rows, cols })
+}
+
+fn read_array<R: Read, const N: usize>(r: &mut R) -> Result<[u8; N], FormatError> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Cell;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn round_trip() -> Result<(), FormatError> {
+        let mut rng = SplitMix64::seed_from_u64(42);
+        let mut grid = SqGrid::with_topology(9, Topology::Cylinder);
+        grid.carve_kruskals(&mut rng);
+        grid.place_entrances().unwrap();
+        let meta = Metadata::new("kruskals", Some(42));
+        let bytes = to_bytes(&grid, &meta)?;
+        // 32 byte header including the 8 byte name and the seed, 81 cells in 21 bytes and two
+        // openings after their count
+        assert_eq!(bytes.len(), 32 + 21 + 4 + 2 * 5);
+        let (copy, copy_meta) = from_bytes(&bytes)?;
+        assert_eq!(copy_meta, meta);
+        assert_eq!(copy.topology(), Topology::Cylinder);
+        assert_eq!(copy.openings(), grid.openings());
+        for (a, b) in grid.iter().zip(copy.iter()) {
+            let mut links = a.links().borrow().clone();
+            let mut copied = b.links().borrow().clone();
+            links.sort();
+            copied.sort();
+            assert_eq!(links, copied);
+        }
+        Ok(())
+    }
+    #[test]
+    fn corrupt_data() {
+        let grid = SqGrid::new(3);
+        grid.link_neighbor(2.into(), &Cardinal::S).unwrap();
+        let mut bytes = to_bytes(&grid, &Metadata::default()).unwrap();
+        assert!(matches!(
+            from_bytes(&bytes[..bytes.len() - 1]),
+            Err(FormatError::Truncated)
+        ));
+        assert!(matches!(from_bytes(b"MAZ"), Err(FormatError::Truncated)));
+        assert!(matches!(
+            from_bytes(b"ZAMEdata"),
+            Err(FormatError::BadMagic)
+        ));
+        for version in [0, 2, 9] {
+            bytes[4] = version;
+            assert!(matches!(
+                from_bytes(&bytes),
+                Err(FormatError::UnsupportedVersion(v)) if v == version
+            ));
+        }
+        bytes[4] = VERSION;
+        // an east link from the last cell of the first row, before the empty list of openings
+        let last = bytes.len() - 4 - 3;
+        bytes[last] |= 1 << 4;
+        assert!(matches!(
+            from_bytes(&bytes),
+            Err(FormatError::InvalidLink(_))
+        ));
+    }
+    #[test]
+    fn bad_header() {
+        let bytes = to_bytes(&SqGrid::new(3), &Metadata::default()).unwrap();
+        let with_size = |rows: u32, cols: u32| {
+            let mut bytes = bytes.clone();
+            bytes[6..10].copy_from_slice(&rows.to_le_bytes());
+            bytes[10..14].copy_from_slice(&cols.to_le_bytes());
+            from_bytes(&bytes)
+        };
+        for size in [0, u32::MAX, 4097] {
+            assert!(matches!(
+                with_size(size, size),
+                Err(FormatError::InvalidSize { .. })
+            ));
+        }
+        assert!(matches!(
+            with_size(3, 4),
+            Err(FormatError::NotSquare { rows: 3, cols: 4 })
+        ));
+        // a valid size whose links are missing
+        assert!(matches!(with_size(4096, 4096), Err(FormatError::Truncated)));
+        // writing checks the same limit, without having to build a grid that large
+        assert_eq!(check_size(4096, 4096).unwrap(), MAX_CELLS);
+        assert!(matches!(
+            check_size(10_000, 10_000),
+            Err(FormatError::InvalidSize { .. })
+        ));
+        assert!(to_bytes(&SqGrid::new(1), &Metadata::default()).is_ok());
+        // a cylinder too narrow to wrap around
+        let mut cylinder = bytes.clone();
+        cylinder[5] = 1;
+        cylinder[6..10].copy_from_slice(&2u32.to_le_bytes());
+        cylinder[10..14].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(
+            from_bytes(&cylinder),
+            Err(FormatError::InvalidSize { rows: 2, cols: 2 })
+        ));
+        // cut off inside the links and inside the openings
+        assert!(matches!(
+            from_bytes(&bytes[..bytes.len() - 5]),
+            Err(FormatError::Truncated)
+        ));
+        assert!(matches!(
+            from_bytes(&bytes[..bytes.len() - 2]),
+            Err(FormatError::Truncated)
+        ));
+    }
+}
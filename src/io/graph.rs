@@ -0,0 +1,203 @@
+//! Exports the maze as a graph for external graph tools, in [Graphviz DOT][dot] or
+//! [GraphML][graphml] format.
+//!
+//! Every cell becomes a node named by its [`Index`], positioned at its [`Coord`] (`x` to the right,
+//! `y` downwards), and every link becomes an undirected edge.  Distances from a [`Distances`] are
+//! added to the nodes they cover and the edges of a [`Path`] are highlighted.
+//!
+//! [dot]: https://graphviz.org/doc/info/lang.html
+//! [graphml]: http://graphml.graphdrawing.org/
+//! [`Coord`]: crate::util::Coord
+
+use crate::maze::{Cell, CoordLookup, Grid, LinkStore};
+use crate::util::dist::Distances;
+use crate::util::path::Path;
+use crate::util::Index;
+use std::collections::HashSet;
+use std::io::Write;
+
+/// Builder for a graph export of a grid, optionally annotated with distances and a path.
+///
+/// # Example
+/// ```
+/// use mazes::io::graph::GraphExport;
+/// use mazes::maze::Grid;
+/// let grid = mazes::SqGrid::new(2);
+/// grid.link(0.into(), 1.into()).unwrap();
+/// let path = grid.shortest_path(0.into(), 1.into());
+/// let dot = GraphExport::new(&grid).path(&path).to_dot();
+/// assert!(dot.contains("0 -- 1 [color=red, penwidth=3];"));
+/// ```
+pub struct GraphExport<'a, 'g, G: Grid> {
+    grid: &'g G,
+    distances: Option<&'a Distances<'g, G>>,
+    path: Option<&'a Path<'g, G>>,
+}
+impl<'a, 'g, G: Grid + CoordLookup> GraphExport<'a, 'g, G> {
+    pub fn new(grid: &'g G) -> Self {
+        Self {
+            grid,
+            distances: None,
+            path: None,
+        }
+    }
+    /// Adds a `dist` attribute to every node with an entry in `distances`.
+    pub fn distances(mut self, distances: &'a Distances<'g, G>) -> Self {
+        self.distances = Some(distances);
+        self
+    }
+    /// Highlights the edges between consecutive cells of `path`.
+    pub fn path(mut self, path: &'a Path<'g, G>) -> Self {
+        self.path = Some(path);
+        self
+    }
+    /// Writes the graph in DOT format.  Nodes are pinned to their coordinates (with `y` negated so
+    /// north is up), which layout engines such as `neato` use as is.
+    pub fn write_dot<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        writeln!(w, "graph maze {{")?;
+        writeln!(w, "    node [shape=point];")?;
+        for id in self.nodes() {
+            let (x, y) = self.grid.get_coords(id).unsigned_tuple();
+            write!(w, "    {} [pos=\"{},-{}!\"", id, x, y)?;
+            if let Some(dist) = self.distance(id) {
+                write!(w, ", dist={}", dist)?;
+            }
+            writeln!(w, "];")?;
+        }
+        let path = self.path_edges();
+        for (a, b) in self.edges() {
+            match path.contains(&(a, b)) {
+                true => writeln!(w, "    {} -- {} [color=red, penwidth=3];", a, b)?,
+                false => writeln!(w, "    {} -- {};", a, b)?,
+            }
+        }
+        writeln!(w, "}}")
+    }
+    /// Writes the graph in GraphML format, with `x`, `y` and `dist` node attributes and a boolean
+    /// `path` edge attribute.
+    pub fn write_graphml<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="x" for="node" attr.name="x" attr.type="int"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="y" for="node" attr.name="y" attr.type="int"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="dist" for="node" attr.name="dist" attr.type="int"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="path" for="edge" attr.name="path" attr.type="boolean">"#
+        )?;
+        writeln!(w, r#"    <default>false</default>"#)?;
+        writeln!(w, r#"  </key>"#)?;
+        writeln!(w, r#"  <graph id="maze" edgedefault="undirected">"#)?;
+        for id in self.nodes() {
+            let (x, y) = self.grid.get_coords(id).unsigned_tuple();
+            writeln!(w, r#"    <node id="n{}">"#, id)?;
+            writeln!(w, r#"      <data key="x">{}</data>"#, x)?;
+            writeln!(w, r#"      <data key="y">{}</data>"#, y)?;
+            if let Some(dist) = self.distance(id) {
+                writeln!(w, r#"      <data key="dist">{}</data>"#, dist)?;
+            }
+            writeln!(w, r#"    </node>"#)?;
+        }
+        let path = self.path_edges();
+        for (a, b) in self.edges() {
+            match path.contains(&(a, b)) {
+                true => {
+                    writeln!(w, r#"    <edge source="n{}" target="n{}">"#, a, b)?;
+                    writeln!(w, r#"      <data key="path">true</data>"#)?;
+                    writeln!(w, r#"    </edge>"#)?;
+                }
+                false => writeln!(w, r#"    <edge source="n{}" target="n{}"/>"#, a, b)?,
+            }
+        }
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")
+    }
+    pub fn to_dot(&self) -> String {
+        let mut out = Vec::new();
+        self.write_dot(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+    pub fn to_graphml(&self) -> String {
+        let mut out = Vec::new();
+        self.write_graphml(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+    fn nodes(&self) -> impl Iterator<Item = Index> + '_ {
+        self.grid.iter().map(|c| c.id())
+    }
+    /// Every link once, with the lower id first.
+    fn edges(&self) -> Vec<(Index, Index)> {
+        let mut edges: Vec<(Index, Index)> = self
+            .grid
+            .iter()
+            .flat_map(|c| {
+                let id = c.id();
+                c.links()
+                    .borrow()
+                    .iter()
+                    .filter(|n| **n > id)
+                    .map(|n| (id, *n))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        edges.sort();
+        edges
+    }
+    fn path_edges(&self) -> HashSet<(Index, Index)> {
+        self.path
+            .map(|p| {
+                p.get_ref()
+                    .windows(2)
+                    .map(|w| (w[0].min(w[1]), w[0].max(w[1])))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    fn distance(&self, id: Index) -> Option<usize> {
+        self.distances.and_then(|d| d.get(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::sq::SqGrid;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn export_graph() {
+        let mut rng = SplitMix64::seed_from_u64(44);
+        let grid = SqGrid::wilsons(4, &mut rng);
+        let dist = grid.distances(0.into());
+        let path = dist.shortest_path(15.into());
+        let export = GraphExport::new(&grid).distances(&dist).path(&path);
+        let dot = export.to_dot();
+        // 16 nodes, 15 links in a perfect maze and the header, node defaults and closing brace
+        assert_eq!(dot.lines().count(), 16 + 15 + 3);
+        assert!(dot.contains("15 [pos=\"3,-3!\""));
+        assert_eq!(dot.matches("color=red").count(), path.len() - 1);
+        let graphml = export.to_graphml();
+        assert_eq!(graphml.matches("<node ").count(), 16);
+        assert_eq!(graphml.matches("<edge ").count(), 15);
+        assert_eq!(
+            graphml.matches(r#"<data key="path">true"#).count(),
+            path.len() - 1
+        );
+        assert_eq!(
+            graphml.matches(r#"<data key="dist">"#).count(),
+            dist.map_ref().len()
+        );
+    }
+}
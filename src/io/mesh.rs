@@ -0,0 +1,191 @@
+//! Extrudes the walls of a [`SqGrid`] into a 3D mesh, written as binary [STL][stl] for 3D
+//! printing or [Wavefront OBJ][obj] for game engines.
+//!
+//! Walls are placed wherever the rendered image would draw them (see
+//! [`Renderable::draw_block_outline`]), each wall becoming a box that overlaps its neighbors at
+//! the corners.  The mesh uses `x` to the east, `y` to the north and `z` up, with the north west
+//! corner of the maze at `(0, rows * cell_size)` so the printed maze is not mirrored.
+//!
+//! [stl]: https://en.wikipedia.org/wiki/STL_(file_format)
+//! [obj]: https://en.wikipedia.org/wiki/Wavefront_.obj_file
+//! [`Renderable::draw_block_outline`]: crate::render::Renderable::draw_block_outline
+
+use crate::maze::sq::{sq_has_wall, SqGrid};
+use crate::maze::{CardinalGrid, Cell, CoordLookup, Grid};
+use crate::util::Cardinal;
+use std::io::Write;
+
+#[derive(Clone, Debug)]
+pub struct MeshOpts {
+    /// Width of a cell, measured between the centers of its walls.
+    pub cell_size: f32,
+    pub wall_height: f32,
+    pub wall_thickness: f32,
+    /// Thickness of the plate under the maze, which extends half a wall past the outer walls.
+    /// No plate is added when this is `0.0`.
+    pub base_height: f32,
+}
+impl Default for MeshOpts {
+    fn default() -> Self {
+        Self {
+            cell_size: 10.0,
+            wall_height: 8.0,
+            wall_thickness: 1.5,
+            base_height: 2.0,
+        }
+    }
+}
+
+/// An indexed triangle mesh.  Triangles are wound counter clockwise seen from outside.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    vertices: Vec<[f32; 3]>,
+    triangles: Vec<[u32; 3]>,
+}
+impl Mesh {
+    /// Builds the walls, and base plate if any, of `grid`.
+    pub fn new(grid: &SqGrid, opts: &MeshOpts) -> Self {
+        let mut mesh = Self::default();
+        let (rows, cols) = grid.dimensions();
+        let (s, half) = (opts.cell_size, opts.wall_thickness / 2.0);
+        let top = *rows as f32 * s;
+        let (z1, z2) = (opts.base_height, opts.base_height + opts.wall_height);
+        if opts.base_height > 0.0 {
+            let right = *cols as f32 * s;
+            mesh.add_box([-half, -half, 0.0], [right + half, top + half, z1]);
+        }
+        for cell in grid.iter() {
+            let id = cell.id();
+            let (col, row) = grid.get_coords(id).float_tuple();
+            let (x1, x2) = (col * s, (col + 1.0) * s);
+            // rows grow southwards while `y` grows northwards
+            let (y1, y2) = (top - (row + 1.0) * s, top - row * s);
+            for d in Cardinal::iter() {
+                // walls between two cells are only added by the cell to the west or north of it
+                let inner = !grid.has_boundary(id, d) && (d == Cardinal::N || d == Cardinal::W);
+                if inner || !sq_has_wall(grid, id, d) {
+                    continue;
+                }
+                let (min, max) = match d {
+                    Cardinal::N => ([x1 - half, y2 - half, z1], [x2 + half, y2 + half, z2]),
+                    Cardinal::S => ([x1 - half, y1 - half, z1], [x2 + half, y1 + half, z2]),
+                    Cardinal::E => ([x2 - half, y1 - half, z1], [x2 + half, y2 + half, z2]),
+                    Cardinal::W => ([x1 - half, y1 - half, z1], [x1 + half, y2 + half, z2]),
+                };
+                mesh.add_box(min, max);
+            }
+        }
+        mesh
+    }
+    pub fn vertices(&self) -> &[[f32; 3]] {
+        &self.vertices
+    }
+    pub fn triangles(&self) -> &[[u32; 3]] {
+        &self.triangles
+    }
+    /// Writes the mesh as binary STL, which stores every triangle with its own vertices.
+    pub fn write_stl<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        let mut header = [0u8; 80];
+        header[..11].copy_from_slice(b"mazes::mesh");
+        w.write_all(&header)?;
+        w.write_all(&(self.triangles.len() as u32).to_le_bytes())?;
+        for t in &self.triangles {
+            let [a, b, c] = t.map(|i| self.vertices[i as usize]);
+            for v in [normal(a, b, c), a, b, c] {
+                for f in v {
+                    w.write_all(&f.to_le_bytes())?;
+                }
+            }
+            w.write_all(&[0, 0])?;
+        }
+        Ok(())
+    }
+    /// Writes the mesh as Wavefront OBJ text.
+    pub fn write_obj<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        writeln!(w, "o maze")?;
+        for [x, y, z] in &self.vertices {
+            writeln!(w, "v {} {} {}", x, y, z)?;
+        }
+        for [a, b, c] in &self.triangles {
+            // OBJ indices start at 1
+            writeln!(w, "f {} {} {}", a + 1, b + 1, c + 1)?;
+        }
+        Ok(())
+    }
+    fn add_box(&mut self, min: [f32; 3], max: [f32; 3]) {
+        let start = self.vertices.len() as u32;
+        for i in 0..8 {
+            let pick = |axis: usize| match i >> axis & 1 {
+                0 => min[axis],
+                _ => max[axis],
+            };
+            self.vertices.push([pick(0), pick(1), pick(2)]);
+        }
+        // two triangles for each face, as corners of the box numbered by bits (x=1, y=2, z=4)
+        const FACES: [[u32; 4]; 6] = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        for [a, b, c, d] in FACES {
+            self.triangles.push([start + a, start + b, start + c]);
+            self.triangles.push([start + a, start + c, start + d]);
+        }
+    }
+}
+
+fn normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    match len > 0.0 {
+        true => n.map(|f| f / len),
+        false => n,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::GridProps;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn maze_mesh() -> std::io::Result<()> {
+        let mut rng = SplitMix64::seed_from_u64(45);
+        let grid = SqGrid::wilsons(6, &mut rng);
+        let mesh = Mesh::new(&grid, &MeshOpts::default());
+        // a perfect maze removes `capacity - 1` of the inner walls: 4 * 6 outer walls,
+        // 2 * 6 * 5 inner walls, and one box for the base plate
+        let walls = 4 * 6 + 2 * 6 * 5 - (*grid.capacity() - 1);
+        assert_eq!(mesh.triangles().len(), (walls + 1) * 12);
+        for t in mesh.triangles() {
+            let [a, b, c] = t.map(|i| mesh.vertices()[i as usize]);
+            let n = normal(a, b, c);
+            // every normal points away from the center of its box
+            let center = t[0] as usize / 8 * 8;
+            let mid: Vec<f32> = (0..3)
+                .map(|k| (mesh.vertices()[center][k] + mesh.vertices()[center + 7][k]) / 2.0)
+                .collect();
+            let out: f32 = (0..3).map(|k| n[k] * (a[k] - mid[k])).sum();
+            assert!(out > 0.0);
+        }
+        let mut stl = Vec::new();
+        mesh.write_stl(&mut stl)?;
+        assert_eq!(stl.len(), 84 + 50 * mesh.triangles().len());
+        let mut obj = Vec::new();
+        mesh.write_obj(&mut obj)?;
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(obj.matches("\nv ").count(), mesh.vertices().len());
+        assert_eq!(obj.matches("\nf ").count(), mesh.triangles().len());
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+//! Reconstructs a [`SqGrid`] from an image made by [`Renderer::save_render`].
+//!
+//! The image is not interpreted as a picture: the cell layout is taken from the
+//! [`BasicOpts`] the maze was rendered with (`frame_size`, `block_size` and `border_width`), so
+//! the same values have to be passed in when loading.  Images that were resized with
+//! [`ScaleImage`] have to be loaded with options matching the final size.
+//!
+//! Each wall is sampled once, at the middle of its side away from any joints.  A pixel counts as
+//! a wall when every channel is within [`TOLERANCE`] of `border_color`, which leaves room for
+//! compression artifacts in scanned or re-encoded images.  Gaps in the outer boundary become
+//! openings (see [`SqGrid::open_boundary`]).
+//!
+//! [`Renderer::save_render`]: crate::render::Renderer::save_render
+//! [`ScaleImage`]: crate::render::opts::ScaleImage
+
+use crate::error::ImageImportError;
+use crate::maze::sq::SqGrid;
+use crate::maze::{CardinalGrid, CoordLookup, GridProps};
+use crate::render::BasicOpts;
+use crate::util::{Cardinal, Index};
+use image::{Rgba, RgbaImage};
+
+/// The largest difference in any color channel for a pixel to still be part of a wall.
+pub const TOLERANCE: u8 = 64;
+
+/// Loads the image at `path` and reconstructs the maze drawn in it.
+pub fn load(path: &std::path::Path, opts: &BasicOpts) -> Result<SqGrid, ImageImportError> {
+    let image = image::open(path)?.to_rgba8();
+    from_image(&image, opts)
+}
+
+/// Reconstructs the maze drawn in `image`.
+pub fn from_image(image: &RgbaImage, opts: &BasicOpts) -> Result<SqGrid, ImageImportError> {
+    let border = opts.border_width();
+    if border == 0 {
+        return Err(ImageImportError::NoBorders);
+    }
+    let size = grid_size(image, opts).ok_or(ImageImportError::Dimensions {
+        width: image.width(),
+        height: image.height(),
+    })?;
+    let step = opts.block_size() + border;
+    // the walls left of (or above) cell `i` start `i` steps from the frame, and each cell's
+    // interior starts one border width after its wall
+    let wall = |i: u32| opts.frame_size() + i * step + border / 2;
+    let middle = |i: u32| opts.frame_size() + i * step + border + opts.block_size() / 2;
+    let is_wall = |x: u32, y: u32| is_border(image.get_pixel(x, y), opts.border_color());
+    let mut grid = SqGrid::new(size as usize);
+    for id in (0..*grid.capacity()).map(Index::from) {
+        let (col, row) = grid.get_coords(id).unsigned_tuple();
+        if col + 1 < size && !is_wall(wall(col + 1), middle(row)) {
+            grid.link_neighbor(id, &Cardinal::E).unwrap();
+        }
+        if row + 1 < size && !is_wall(middle(col), wall(row + 1)) {
+            grid.link_neighbor(id, &Cardinal::S).unwrap();
+        }
+        for d in Cardinal::iter() {
+            if !grid.has_boundary(id, d) {
+                continue;
+            }
+            let (x, y) = match d {
+                Cardinal::N => (middle(col), wall(0)),
+                Cardinal::E => (wall(size), middle(row)),
+                Cardinal::S => (middle(col), wall(size)),
+                Cardinal::W => (wall(0), middle(row)),
+            };
+            if !is_wall(x, y) {
+                grid.open_boundary(id, d).unwrap();
+            }
+        }
+    }
+    Ok(grid)
+}
+
+/// The number of cells in each row, if the image has the dimensions of a rendered square grid.
+fn grid_size(image: &RgbaImage, opts: &BasicOpts) -> Option<u32> {
+    let step = opts.block_size() + opts.border_width();
+    let outside = 2 * opts.frame_size() + opts.border_width();
+    let inner = image.width().checked_sub(outside)?;
+    match image.width() == image.height() && inner > 0 && inner % step == 0 {
+        true => Some(inner / step),
+        false => None,
+    }
+}
+
+fn is_border(pixel: &Rgba<u8>, border: &Rgba<u8>) -> bool {
+    pixel
+        .0
+        .iter()
+        .zip(border.0.iter())
+        .take(3)
+        .all(|(a, b)| a.abs_diff(*b) <= TOLERANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{Cell, Grid};
+    use crate::render::Renderer;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn import_render() -> Result<(), ImageImportError> {
+        let mut rng = SplitMix64::seed_from_u64(43);
+        let mut grid = SqGrid::wilsons(12, &mut rng);
+        grid.open_boundary(5.into(), Cardinal::N).unwrap();
+        grid.open_boundary(143.into(), Cardinal::E).unwrap();
+        let mut opts = BasicOpts::default();
+        opts.set_border_width(3);
+        opts.set_frame_size(7);
+        let image = grid.render_options(&opts).render_rgba();
+        let copy = from_image(&image, &opts)?;
+        for (a, b) in grid.iter().zip(copy.iter()) {
+            let mut links = a.links().borrow().clone();
+            let mut copied = b.links().borrow().clone();
+            links.sort();
+            copied.sort();
+            assert_eq!(links, copied);
+        }
+        assert_eq!(copy.openings(), grid.openings());
+        let wrong = BasicOpts::default();
+        assert!(matches!(
+            from_image(&image, &wrong),
+            Err(ImageImportError::Dimensions { .. })
+        ));
+        Ok(())
+    }
+}
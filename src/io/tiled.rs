@@ -0,0 +1,299 @@
+//! Exports a [`SqGrid`] as a tilemap for the [Tiled][tiled] map editor, as TMX or JSON.
+//!
+//! Each cell becomes a block of `k×k` tiles: the tiles along the sides of the block are walls
+//! wherever the rendered image would draw a wall, and floor everywhere else.  The corners of a
+//! block are always walls, so passages between cells are `k - 2` tiles wide and walls between cells
+//! are two tiles thick.
+//!
+//! The map refers to an external tileset (a `.tsx` file) holding the wall and floor tiles; tile
+//! ids are Tiled's global ids, which start at the tileset's `firstgid` of `1`.  Entity markers
+//! for the start and end of a [`Path`] are written as point objects in a separate object layer.
+//!
+//! [tiled]: https://www.mapeditor.org/
+
+use crate::maze::sq::{sq_has_wall, SqGrid};
+use crate::maze::{CardinalGrid, CoordLookup, GridProps};
+use crate::util::path::Path;
+use crate::util::{Cardinal, Index};
+use std::io::Write;
+
+#[derive(Clone, Debug)]
+pub struct TilemapOpts {
+    /// The number of tiles along each side of a cell, at least 3.
+    pub cell_tiles: u32,
+    /// Width and height of a tile in pixels.
+    pub tile_size: u32,
+    pub wall_tile: u32,
+    pub floor_tile: u32,
+    /// Path of the tileset file, relative to the exported map.
+    pub tileset: String,
+}
+impl Default for TilemapOpts {
+    fn default() -> Self {
+        Self {
+            cell_tiles: 3,
+            tile_size: 16,
+            wall_tile: 1,
+            floor_tile: 2,
+            tileset: "maze.tsx".to_string(),
+        }
+    }
+}
+
+/// A point object placed at the center of a cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Marker {
+    pub name: String,
+    pub cell: Index,
+    /// Position in pixels.
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Tilemap {
+    width: u32,
+    height: u32,
+    tile_size: u32,
+    tileset: String,
+    tiles: Vec<u32>,
+    markers: Vec<Marker>,
+}
+impl Tilemap {
+    /// Builds the tile layer of `grid`.
+    ///
+    /// Panics if `opts.cell_tiles` is less than 3, which leaves no room for passages.
+    pub fn new(grid: &SqGrid, opts: &TilemapOpts) -> Self {
+        let k = opts.cell_tiles;
+        assert!(k >= 3, "cells need at least 3 tiles per side, got {}", k);
+        let (rows, cols) = grid.dimensions();
+        let (width, height) = (*cols as u32 * k, *rows as u32 * k);
+        let mut tiles = vec![opts.floor_tile; (width * height) as usize];
+        for id in (0..*grid.capacity()).map(Index::from) {
+            let (col, row) = grid.get_coords(id).unsigned_tuple();
+            for j in 0..k {
+                for i in 0..k {
+                    let corner = (i == 0 || i == k - 1) && (j == 0 || j == k - 1);
+                    let side = match (i, j) {
+                        (_, 0) => Cardinal::N,
+                        (_, j) if j == k - 1 => Cardinal::S,
+                        (0, _) => Cardinal::W,
+                        (i, _) if i == k - 1 => Cardinal::E,
+                        _ => continue,
+                    };
+                    if corner || sq_has_wall(grid, id, side) {
+                        let (x, y) = (col * k + i, row * k + j);
+                        tiles[(y * width + x) as usize] = opts.wall_tile;
+                    }
+                }
+            }
+        }
+        Self {
+            width,
+            height,
+            tile_size: opts.tile_size,
+            tileset: opts.tileset.clone(),
+            tiles,
+            markers: Vec::new(),
+        }
+    }
+    /// Adds a marker named `name` at the center of `cell`.
+    pub fn mark(&mut self, grid: &SqGrid, cell: Index, name: &str) {
+        let (col, row) = grid.get_coords(cell).unsigned_tuple();
+        let block = self.width / *grid.dimensions().1 as u32 * self.tile_size;
+        self.markers.push(Marker {
+            name: name.to_string(),
+            cell,
+            x: col * block + block / 2,
+            y: row * block + block / 2,
+        });
+    }
+    /// Adds `start` and `end` markers at [`Path::first`] and [`Path::last`].
+    pub fn mark_path(&mut self, path: &Path<SqGrid>) {
+        if path.is_empty() {
+            return;
+        }
+        self.mark(path.grid(), path.first(), "start");
+        self.mark(path.grid(), path.last(), "end");
+    }
+    /// Width of the map in tiles.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    /// Height of the map in tiles.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    /// The tile at column `x` and row `y` of the map.
+    pub fn tile(&self, x: u32, y: u32) -> u32 {
+        self.tiles[(y * self.width + x) as usize]
+    }
+    pub fn markers(&self) -> &[Marker] {
+        &self.markers
+    }
+    /// Writes the map as a TMX (XML) file with CSV encoded tile data.
+    pub fn write_tmx<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<map version="1.10" orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="0" nextlayerid="3" nextobjectid="{}">"#,
+            self.width,
+            self.height,
+            self.tile_size,
+            self.tile_size,
+            self.markers.len() + 1
+        )?;
+        writeln!(
+            w,
+            r#" <tileset firstgid="1" source="{}"/>"#,
+            escape_xml(&self.tileset)
+        )?;
+        writeln!(
+            w,
+            r#" <layer id="1" name="maze" width="{}" height="{}">"#,
+            self.width, self.height
+        )?;
+        writeln!(w, r#"  <data encoding="csv">"#)?;
+        let rows: Vec<String> = self
+            .tiles
+            .chunks(self.width as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect();
+        writeln!(w, "{}", rows.join(",\n"))?;
+        writeln!(w, "</data>")?;
+        writeln!(w, " </layer>")?;
+        writeln!(w, r#" <objectgroup id="2" name="entities">"#)?;
+        for (i, m) in self.markers.iter().enumerate() {
+            writeln!(
+                w,
+                r#"  <object id="{}" name="{}" type="{}" x="{}" y="{}"><point/></object>"#,
+                i + 1,
+                escape_xml(&m.name),
+                escape_xml(&m.name),
+                m.x,
+                m.y
+            )?;
+        }
+        writeln!(w, " </objectgroup>")?;
+        writeln!(w, "</map>")
+    }
+    /// Writes the map in Tiled's JSON format.
+    pub fn write_json<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        let data: Vec<String> = self.tiles.iter().map(|t| t.to_string()).collect();
+        let objects: Vec<String> = self
+            .markers
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                format!(
+                    r#"{{"id":{},"name":"{}","type":"{}","x":{},"y":{},"width":0,"height":0,"rotation":0,"point":true,"visible":true}}"#,
+                    i + 1,
+                    escape_json(&m.name),
+                    escape_json(&m.name),
+                    m.x,
+                    m.y
+                )
+            })
+            .collect();
+        write!(
+            w,
+            r#"{{"type":"map","version":"1.10","orientation":"orthogonal","renderorder":"right-down","width":{},"height":{},"tilewidth":{},"tileheight":{},"infinite":false,"nextlayerid":3,"nextobjectid":{},"#,
+            self.width,
+            self.height,
+            self.tile_size,
+            self.tile_size,
+            self.markers.len() + 1
+        )?;
+        write!(
+            w,
+            r#""tilesets":[{{"firstgid":1,"source":"{}"}}],"#,
+            escape_json(&self.tileset)
+        )?;
+        write!(
+            w,
+            r#""layers":[{{"id":1,"name":"maze","type":"tilelayer","width":{},"height":{},"x":0,"y":0,"opacity":1,"visible":true,"data":[{}]}},"#,
+            self.width,
+            self.height,
+            data.join(",")
+        )?;
+        writeln!(
+            w,
+            r#"{{"id":2,"name":"entities","type":"objectgroup","draworder":"topdown","x":0,"y":0,"opacity":1,"visible":true,"objects":[{}]}}]}}"#,
+            objects.join(",")
+        )
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_json(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Grid;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn export_tilemap() -> std::io::Result<()> {
+        let grid = SqGrid::new(2);
+        grid.link_neighbor(0.into(), &Cardinal::E).unwrap();
+        grid.link_neighbor(1.into(), &Cardinal::S).unwrap();
+        let opts = TilemapOpts::default();
+        let mut map = Tilemap::new(&grid, &opts);
+        let path = grid.shortest_path(0.into(), 3.into());
+        map.mark_path(&path);
+        let (w, f) = (opts.wall_tile, opts.floor_tile);
+        let rows: Vec<Vec<u32>> = (0..6)
+            .map(|y| (0..6).map(|x| map.tile(x, y)).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![w, w, w, w, w, w],
+                vec![w, f, f, f, f, w],
+                vec![w, w, w, w, f, w],
+                vec![w, w, w, w, f, w],
+                vec![w, f, w, w, f, w],
+                vec![w, w, w, w, w, w],
+            ]
+        );
+        assert_eq!(map.markers()[0].cell, 0.into());
+        assert_eq!((map.markers()[1].x, map.markers()[1].y), (72, 72));
+        let mut tmx = Vec::new();
+        map.write_tmx(&mut tmx)?;
+        let tmx = String::from_utf8(tmx).unwrap();
+        assert!(tmx.contains(r#"name="end" type="end" x="72" y="72""#));
+        assert_eq!(tmx.matches("\n1,1,1,1,1,1").count(), 2);
+        Ok(())
+    }
+    #[test]
+    fn export_json() -> std::io::Result<()> {
+        let mut rng = SplitMix64::seed_from_u64(46);
+        let grid = SqGrid::wilsons(5, &mut rng);
+        let opts = TilemapOpts {
+            cell_tiles: 4,
+            ..TilemapOpts::default()
+        };
+        let map = Tilemap::new(&grid, &opts);
+        let mut json = Vec::new();
+        map.write_json(&mut json)?;
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["width"], 20);
+        assert_eq!(json["layers"][0]["data"].as_array().unwrap().len(), 400);
+        assert!(json["layers"][1]["objects"].as_array().unwrap().is_empty());
+        Ok(())
+    }
+}
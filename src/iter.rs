@@ -114,7 +114,7 @@ impl<'g, 'r, R: Rng + ?Sized, G: Grid> Rand<'g, 'r, R, G> {
         self.grid.lookup(self.id_from_list(list))
     }
     pub fn random_neighbor_id(&mut self, cell: &'g G::C) -> Index {
-        self.id_from_list(&*cell.neighbor_ids())
+        self.id_from_list(&cell.neighbor_ids())
     }
 }
 impl<'g, 'r, R: Rng + ?Sized, G: Grid> Iterator for Rand<'g, 'r, R, G> {
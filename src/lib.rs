@@ -1,5 +1,7 @@
 pub mod algo;
+pub mod analysis;
 pub mod error;
+pub mod io;
 pub mod iter;
 pub mod maze;
 pub mod render;
@@ -1,16 +1,28 @@
+pub mod cube;
+pub mod levels;
 pub mod rect;
 pub mod sq;
+pub mod weave;
 
-pub use self::rect::CardinalGrid;
+pub use self::rect::{CardinalGrid, Topology};
+use crate::algo::astar::{astar, Heuristic};
+use crate::algo::bfs::{bfs_path, bidirectional_path};
+use crate::algo::kruskals::{link_sets, DisjointSets};
+use crate::analysis::Validation;
 use crate::error::*;
 use crate::iter::*;
 use crate::trans::*;
 use crate::util::dist::Distances;
 use crate::util::path::Path;
+use crate::util::regions::Regions;
 use crate::util::*;
 use linked_hash_set::LinkedHashSet;
 use rand::Rng;
 use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::Deref;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
 
 /// Trait to access fields of the implementing type.  These methods were separated from [`Grid`]
 /// to allow more generic use cases.
@@ -25,7 +37,7 @@ pub trait Grid: GridProps {
             Err(OutOfBoundsError::new(id))
         }
     }
-    fn link(&self, a: Index, b: Index) -> Result<(), CellLinkError> {
+    fn link(&self, a: Index, b: Index) -> Result<(), CellLinkError<'_>> {
         let cell_a = self
             .get(a)
             .ok_or_else(|| CellLinkError::new(a, b, "Link failed - `a` could not be retrieved"))?;
@@ -36,7 +48,7 @@ pub trait Grid: GridProps {
         cell_b.unchecked_link(a);
         Ok(())
     }
-    fn unlink(&self, a: Index, b: Index) -> Result<(), CellLinkError> {
+    fn unlink(&self, a: Index, b: Index) -> Result<(), CellLinkError<'_>> {
         let cell_a = self
             .get(a)
             .ok_or_else(|| CellLinkError::new(a, b, "Unlink failed - `a` not found"))?;
@@ -49,14 +61,14 @@ pub trait Grid: GridProps {
     }
     /// Produces an [`Iter`] to iterate the grid using the [`Ident`] transform, which does
     /// not change iteration order while still allowing [`Iter`] to be generic over `T: Transform`.
-    fn iter(&self) -> Iter<Self, Ident>
+    fn iter(&self) -> Iter<'_, Self, Ident>
     where
         Self: Sized + Grid,
     {
         Iter::new(self, Ident)
     }
     /// Produces a [`Rev`] iterator to reverse the direction of iteration.
-    fn reverse(&self) -> Iter<Self, Rev>
+    fn reverse(&self) -> Iter<'_, Self, Rev>
     where
         Self: Sized + Grid,
     {
@@ -127,12 +139,78 @@ pub trait Grid: GridProps {
         }
         dist
     }
+    /// Like [`Grid::distances`], but moving into a cell costs `weight(id)` instead of a single
+    /// step, which allows modelling terrain that is slower to cross.  Uses Dijkstra's algorithm.
+    ///
+    /// Weights of zero are treated as one so that paths can always be traced back to `start`.
+    fn weighted_distances<F>(&self, start: Index, weight: F) -> Distances<'_, Self>
+    where
+        Self: Sized,
+        F: Fn(Index) -> usize,
+    {
+        let mut dist = Distances::new(self, start);
+        let mut frontier = BinaryHeap::new();
+        frontier.push(Reverse((0, start)));
+        while let Some(Reverse((d, id))) = frontier.pop() {
+            if d > dist[id] {
+                continue;
+            }
+            for link in self.lookup(id).links().borrow().iter() {
+                let next = d + weight(*link).max(1);
+                if dist.get(*link).is_none_or(|old| next < old) {
+                    dist[*link] = next;
+                    frontier.push(Reverse((next, *link)));
+                }
+            }
+        }
+        dist
+    }
+    /// Checks the maze for problems such as unreachable cells, loops and links that only go one
+    /// way.  See [`Validation`].
+    fn validate(&self) -> Validation
+    where
+        Self: Sized,
+    {
+        Validation::new(self)
+    }
+    /// Computes the distance of every cell to the closest of several `seeds` and which seed
+    /// that is.  See [`Regions`].
+    fn regions(&self, seeds: &[Index]) -> Regions<'_, Self>
+    where
+        Self: Sized,
+    {
+        Regions::new(self, seeds)
+    }
     fn shortest_path(&self, start: Index, end: Index) -> Path<'_, Self>
     where
         Self: Sized,
     {
         self.distances(start).shortest_path(end)
     }
+    /// Returns the shortest path from `start` to `end`, stopping the search as soon as `end` is
+    /// reached.  Returns `None` if there is no route between the cells.
+    fn bfs_path(&self, start: Index, end: Index) -> Option<Path<'_, Self>>
+    where
+        Self: Sized,
+    {
+        bfs_path(self, start, end)
+    }
+    /// Returns the shortest path from `start` to `end`, searching from both ends until they meet.
+    /// Returns `None` if there is no route between the cells.
+    fn bidirectional_path(&self, start: Index, end: Index) -> Option<Path<'_, Self>>
+    where
+        Self: Sized,
+    {
+        bidirectional_path(self, start, end)
+    }
+    /// Returns the cheapest path from `start` to `end` using [`Grid::weighted_distances`].
+    fn weighted_shortest_path<F>(&self, start: Index, end: Index, weight: F) -> Path<'_, Self>
+    where
+        Self: Sized,
+        F: Fn(Index) -> usize,
+    {
+        self.weighted_distances(start, weight).shortest_path(end)
+    }
     fn longest_path(&self, start: Index) -> Path<'_, Self>
     where
         Self: Sized,
@@ -154,17 +232,25 @@ pub trait Grid: GridProps {
         Self: Sized,
     {
         let grid = Self::setup(size);
-        let mut cell = grid.random_id(rng);
-        let mut unvisited = grid.capacity().minus(1usize);
+        grid.carve_aldous_broder(rng);
+        grid
+    }
+    /// Links the cells of an existing grid using [`Grid::aldous_broder`], e.g. a grid created with a
+    /// wrapping [`Topology`].  The grid should not contain any links beforehand.
+    fn carve_aldous_broder<R: Rng + ?Sized>(&self, rng: &mut R)
+    where
+        Self: Sized,
+    {
+        let mut cell = self.random_id(rng);
+        let mut unvisited = self.capacity().minus(1usize);
         while unvisited > 0 {
-            let neighbor = grid.random_neighbor(cell, rng);
+            let neighbor = self.random_neighbor(cell, rng);
             if neighbor.not_linked() {
-                grid.link(cell, neighbor.id()).unwrap();
+                self.link(cell, neighbor.id()).unwrap();
                 unvisited -= 1;
             }
             cell = neighbor.id();
         }
-        grid
     }
     /// An implementation of [Wilson's Algorithm][wilsons].  It uses a loop-erased walk.
     ///
@@ -176,7 +262,16 @@ pub trait Grid: GridProps {
         Self: Sized,
     {
         let grid = Self::setup(size);
-        let mut unvisited: LinkedHashSet<Index> = (0..*grid.capacity()).map(Index::from).collect();
+        grid.carve_wilsons(rng);
+        grid
+    }
+    /// Links the cells of an existing grid using [`Grid::wilsons`], e.g. a grid created with a
+    /// wrapping [`Topology`].  The grid should not contain any links beforehand.
+    fn carve_wilsons<R: Rng + ?Sized>(&self, rng: &mut R)
+    where
+        Self: Sized,
+    {
+        let mut unvisited: LinkedHashSet<Index> = (0..*self.capacity()).map(Index::from).collect();
         let first = *unvisited
             .iter()
             .nth(rng.gen_range(0..unvisited.len()))
@@ -189,7 +284,7 @@ pub trait Grid: GridProps {
                 .unwrap();
             let mut path = vec![cell];
             while unvisited.contains(&cell) {
-                cell = grid.random_neighbor_id(cell, rng);
+                cell = self.random_neighbor_id(cell, rng);
                 if let Some(pos) = path.iter().position(|i| *i == cell) {
                     path.truncate(pos + 1);
                 } else {
@@ -198,12 +293,101 @@ pub trait Grid: GridProps {
             }
             for i in 0..path.len() - 1 {
                 let a = path[i];
-                grid.link(a, path[i + 1]).unwrap();
+                self.link(a, path[i + 1]).unwrap();
                 unvisited.remove(&a);
             }
         }
+    }
+    /// An implementation of [Kruskal's Algorithm][kruskals].  Each cell starts in its own set
+    /// and pairs of neighbors are linked in random order whenever they belong to different sets.
+    ///
+    /// See also: [Kruskal's Algorithm][kruskals]
+    ///
+    /// [kruskals]: https://en.wikipedia.org/wiki/Maze_generation_algorithm#Randomized_Kruskal's_algorithm
+    fn kruskals<R: Rng + ?Sized>(size: usize, rng: &mut R) -> Self
+    where
+        Self: Sized,
+    {
+        let grid = Self::setup(size);
+        grid.carve_kruskals(rng);
         grid
     }
+    /// Links the cells of an existing grid using [`Grid::kruskals`], e.g. a grid created with a
+    /// wrapping [`Topology`].  The grid should not contain any links beforehand.
+    fn carve_kruskals<R: Rng + ?Sized>(&self, rng: &mut R)
+    where
+        Self: Sized,
+    {
+        let mut sets = DisjointSets::new(*self.capacity());
+        link_sets(self, &mut sets, rng);
+    }
+    /// Ids of all cells with exactly one link.
+    fn dead_ends(&self) -> Vec<Index> {
+        self.cells()
+            .iter()
+            .filter(|c| c.links().borrow().len() == 1)
+            .map(|c| c.id())
+            .collect()
+    }
+    /// Removes dead ends by linking them to a neighbor they are not linked to yet, which adds
+    /// loops to the maze.  Each dead end is removed with the probability `p`, from `0.0` to
+    /// `1.0`.  Neighbors that are dead ends themselves are preferred, so a single link can remove
+    /// two dead ends.  Returns the number of links added.
+    fn braid<R: Rng + ?Sized>(&self, p: f64, rng: &mut R) -> usize
+    where
+        Self: Sized,
+    {
+        use rand::seq::SliceRandom;
+        let mut dead_ends = self.dead_ends();
+        dead_ends.shuffle(rng);
+        let mut added = 0;
+        for id in dead_ends {
+            // an earlier link may already have removed this dead end
+            if self.lookup(id).links().borrow().len() != 1 || !rng.gen_bool(p) {
+                continue;
+            }
+            let cell = self.lookup(id);
+            let unlinked: Vec<Index> = cell
+                .neighbor_ids()
+                .iter()
+                .copied()
+                .filter(|n| !cell.has_link(*n))
+                .collect();
+            let best: Vec<Index> = unlinked
+                .iter()
+                .copied()
+                .filter(|n| self.lookup(*n).links().borrow().len() == 1)
+                .collect();
+            let choices = if best.is_empty() { &unlinked } else { &best };
+            if let Some(n) = choices.choose(rng) {
+                self.link(id, *n).unwrap();
+                added += 1;
+            }
+        }
+        added
+    }
+    /// Fills in dead ends by unlinking them from the rest of the maze, leaving them without any
+    /// links.  Each dead end is filled with the probability `p`, from `0.0` to `1.0`.  The cells
+    /// that become dead ends as a result are left alone, so calling this repeatedly shortens
+    /// every branch one cell at a time.  Returns the number of cells filled.
+    fn cull<R: Rng + ?Sized>(&self, p: f64, rng: &mut R) -> usize
+    where
+        Self: Sized,
+    {
+        let mut filled = 0;
+        for id in self.dead_ends() {
+            if !rng.gen_bool(p) {
+                continue;
+            }
+            let link = self.lookup(id).links().borrow()[0];
+            // the neighbor may have been a dead end filled earlier in this pass
+            if self.lookup(link).links().borrow().len() > 1 {
+                self.unlink(id, link).unwrap();
+                filled += 1;
+            }
+        }
+        filled
+    }
 }
 
 /// Helper methods that make the [`Grid`] trait actually work.
@@ -223,13 +407,20 @@ pub trait GridProps {
 ///
 /// `Cell` is intentionally very simple to allow more flexibility in regards to grid types.
 pub trait Cell {
+    /// Storage for the ids of linked cells, see [`LinkStore`].
+    type Links: LinkStore;
+    /// The list of neighbors returned by [`neighbor_ids`](Cell::neighbor_ids): a slice for cells
+    /// that store their neighbors, or a value computed when asked for cells that do not.
+    type Neighbors<'a>: Deref<Target = [Index]>
+    where
+        Self: 'a;
     fn id(&self) -> Index;
     /// List cells that are near the current cell, without regards to whether they are linked.
-    fn neighbor_ids(&self) -> &[Index];
+    fn neighbor_ids(&self) -> Self::Neighbors<'_>;
     /// Link a cell with another cell
     fn unchecked_link(&self, with: Index);
     // Return ids of neighboring cells linked with the current cell.
-    fn links(&self) -> &RefCell<Vec<Index>>;
+    fn links(&self) -> &Self::Links;
     fn has_link(&self, link: Index) -> bool {
         self.links().borrow().contains(&link)
     }
@@ -245,19 +436,89 @@ pub trait Cell {
     }
 }
 
+/// Storage for the links of a [`Cell`], which are changed through a shared reference.
+///
+/// [`RefCell`] is the fast single threaded storage used by most grids, while [`RwLock`] makes a
+/// grid `Send + Sync` so it can be shared between threads (see [`sq::SyncSqGrid`]).
+pub trait LinkStore: Default {
+    type Ref<'a>: Deref<Target = [Index]>
+    where
+        Self: 'a;
+    fn borrow(&self) -> Self::Ref<'_>;
+    fn push(&self, id: Index);
+    /// Removes the first link to `id`, if any.
+    fn remove(&self, id: Index);
+}
+impl LinkStore for RefCell<Vec<Index>> {
+    type Ref<'a> = std::cell::Ref<'a, [Index]>;
+    fn borrow(&self) -> Self::Ref<'_> {
+        std::cell::Ref::map(RefCell::borrow(self), Vec::as_slice)
+    }
+    fn push(&self, id: Index) {
+        self.borrow_mut().push(id);
+    }
+    fn remove(&self, id: Index) {
+        let mut links = self.borrow_mut();
+        if let Some(pos) = links.iter().position(|n| *n == id) {
+            links.remove(pos);
+        }
+    }
+}
+/// Links are plain ids, so a panic while holding the lock can not leave them half written and
+/// poisoning is ignored.
+impl LinkStore for RwLock<Vec<Index>> {
+    type Ref<'a> = LinksReadGuard<'a>;
+    fn borrow(&self) -> Self::Ref<'_> {
+        LinksReadGuard(self.read().unwrap_or_else(PoisonError::into_inner))
+    }
+    fn push(&self, id: Index) {
+        self.write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(id);
+    }
+    fn remove(&self, id: Index) {
+        let mut links = self.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(pos) = links.iter().position(|n| *n == id) {
+            links.remove(pos);
+        }
+    }
+}
+
+/// The links of a [`RwLock`] store, read locked for as long as the guard is held.
+pub struct LinksReadGuard<'a>(RwLockReadGuard<'a, Vec<Index>>);
+impl Deref for LinksReadGuard<'_> {
+    type Target = [Index];
+    fn deref(&self) -> &[Index] {
+        &self.0
+    }
+}
+
 /// Any `Grid` type implementing `CoordLookup` can use `Coord` to lookup a cell's `Index`
 pub trait CoordLookup: Grid {
     fn get_id(&self, coord: &Coord) -> Index;
     fn try_get_id(&self, coord: &Coord) -> Result<Index, OutOfBoundsCoordError>;
     fn get_coords(&self, id: Index) -> Coord;
     fn try_get_coords(&self, id: Index) -> Result<Coord, OutOfBoundsError>;
+    /// Finds the shortest path from `start` to `end` using [A* search][astar], which only
+    /// explores the cells needed instead of computing [`Grid::distances`] for the whole grid.
+    /// Returns `None` if there is no route between the cells.
+    ///
+    /// [astar]: https://en.wikipedia.org/wiki/A*_search_algorithm
+    fn astar<H>(&self, start: Index, end: Index, heuristic: &H) -> Option<Path<'_, Self>>
+    where
+        Self: Sized,
+        H: Heuristic<Self> + ?Sized,
+    {
+        astar(self, start, end, heuristic)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::maze::sq::SqGrid;
-    use crate::maze::Grid;
+    use crate::maze::{Cell, Grid, GridProps};
     use crate::render::Renderer;
+    use crate::util::Index;
     use rand::SeedableRng;
     use rand_xoshiro::SplitMix64;
 
@@ -275,4 +536,52 @@ mod test {
         grid.render_defaults()
             .save_render(std::path::Path::new("wilsons.png"))
     }
+    #[test]
+    fn kruskals() -> Result<(), image::ImageError> {
+        let mut rng = SplitMix64::seed_from_u64(852);
+        let grid = SqGrid::kruskals(7, &mut rng);
+        grid.render_defaults()
+            .save_render(std::path::Path::new("kruskals.png"))
+    }
+    #[test]
+    fn braid_and_cull() -> Result<(), image::ImageError> {
+        let mut rng = SplitMix64::seed_from_u64(37);
+        let grid = SqGrid::wilsons(10, &mut rng);
+        let before = grid.dead_ends().len();
+        let added = grid.braid(1.0, &mut rng);
+        assert!(added > 0 && added <= before);
+        assert!(grid.dead_ends().is_empty());
+        assert!(grid.validate().is_connected());
+        assert_eq!(grid.validate().loops.len(), added);
+        let tree = SqGrid::wilsons(10, &mut rng);
+        let dead_ends = tree.dead_ends().len();
+        let filled = tree.cull(1.0, &mut rng);
+        assert_eq!(filled, dead_ends);
+        assert_eq!(tree.validate().isolated.len(), filled);
+        grid.render_defaults()
+            .save_render(std::path::Path::new("braid.png"))
+    }
+    #[test]
+    fn weighted_distances() -> Result<(), image::ImageError> {
+        let mut rng = SplitMix64::seed_from_u64(30);
+        let grid = SqGrid::setup(6);
+        grid.carve_wilsons(&mut rng);
+        // an extra passage makes a loop, so the cheapest route avoids the costly cell
+        for n in [1, 6].map(Index::from) {
+            if !grid.lookup(0.into()).has_link(n) {
+                grid.link(0.into(), n).unwrap();
+            }
+        }
+        let weight = |id: Index| if *id == 1 || *id == 14 { 9 } else { 1 };
+        let dist = grid.weighted_distances(0.into(), weight);
+        let plain = grid.distances(0.into());
+        for (id, d) in plain.map_ref() {
+            assert!(dist[*id] >= *d);
+        }
+        let path = grid.weighted_shortest_path(0.into(), 35.into(), weight);
+        let cost: usize = path.iter().skip(1).map(|id| weight(*id)).sum();
+        assert_eq!(cost, dist[35.into()]);
+        dist.render_defaults()
+            .save_render(std::path::Path::new("weighted_distances.png"))
+    }
 }
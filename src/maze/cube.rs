@@ -0,0 +1,381 @@
+use crate::error::*;
+use crate::maze::sq::SqCell;
+use crate::maze::sq::{
+    draw_sq_block_text, draw_sq_joint, draw_sq_outline, fill_sq_block_bg, sq_band_rows,
+};
+use crate::maze::{CardinalGrid, CoordLookup, Grid, GridProps};
+use crate::render::blocks::UnsignedIntBlock;
+use crate::render::renderers::RenderGrid;
+use crate::render::{BasicOpts, Renderable};
+use crate::util::*;
+use image::{Rgba, RgbaImage};
+use parse_display::Display;
+use std::ops::Range;
+
+/// One of the six faces of a [`CubeGrid`].
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum Face {
+    Up,
+    Left,
+    Front,
+    Right,
+    Back,
+    Down,
+}
+impl Face {
+    pub const ALL: [Face; 6] = [
+        Face::Up,
+        Face::Left,
+        Face::Front,
+        Face::Right,
+        Face::Back,
+        Face::Down,
+    ];
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+    /// Position of the face in the unfolded net, in faces from the top left corner.
+    ///
+    /// ```text
+    ///        Up
+    /// Left  Front  Right  Back
+    ///       Down
+    /// ```
+    pub fn net_position(&self) -> (usize, usize) {
+        match self {
+            Self::Up => (1, 0),
+            Self::Left => (0, 1),
+            Self::Front => (1, 1),
+            Self::Right => (2, 1),
+            Self::Back => (3, 1),
+            Self::Down => (1, 2),
+        }
+    }
+    /// Orientation of the face on a cube centered at the origin, with `x` pointing right, `y`
+    /// pointing down and `z` pointing away from the viewer: the outward normal, and the
+    /// directions of increasing column and row within the face.  The directions match the
+    /// unfolded net, so faces that touch in the net share the same edge on the cube.
+    fn axes(&self) -> ([i64; 3], [i64; 3], [i64; 3]) {
+        match self {
+            Self::Up => ([0, -1, 0], [1, 0, 0], [0, 0, -1]),
+            Self::Left => ([-1, 0, 0], [0, 0, -1], [0, 1, 0]),
+            Self::Front => ([0, 0, -1], [1, 0, 0], [0, 1, 0]),
+            Self::Right => ([1, 0, 0], [0, 0, 1], [0, 1, 0]),
+            Self::Back => ([0, 0, 1], [-1, 0, 0], [0, 1, 0]),
+            Self::Down => ([0, 1, 0], [1, 0, 0], [0, 0, 1]),
+        }
+    }
+    fn from_normal(normal: [i64; 3]) -> Self {
+        *Self::ALL.iter().find(|f| f.axes().0 == normal).unwrap()
+    }
+}
+
+/// A grid covering the surface of a cube with six square faces of `size * size` cells.  Cells on
+/// the edge of a face neighbor the cells on the adjoining face, so there are no boundaries.
+///
+/// Cells are stored face by face in the order of [`Face::ALL`].  [`CoordLookup`] works with
+/// coordinates in the unfolded net (see [`Face::net_position`]), which is also how the grid is
+/// rendered.  Passages that cross from one face to another are drawn in
+/// [`BasicOpts::wrap_color`].
+#[derive(Clone, Debug)]
+pub struct CubeGrid {
+    size: RowSize,
+    cells: Vec<SqCell>,
+}
+impl CubeGrid {
+    pub fn new(size: usize) -> Self {
+        let mut grid = Self {
+            size: size.into(),
+            cells: Vec::with_capacity(size * size * 6),
+        };
+        for id in (0..size * size * 6).map(Index::from) {
+            let neighbors = Cardinal::iter()
+                .filter_map(|d| grid.calc_dir(id, &d))
+                .collect();
+            grid.cells.push(SqCell::new(id, neighbors));
+        }
+        grid
+    }
+    /// Number of cells on each face.
+    pub fn face_capacity(&self) -> Capacity {
+        self.size.cap()
+    }
+    /// Returns the face the cell is on.
+    pub fn face(&self, id: Index) -> Face {
+        Face::ALL[id.div(self.face_capacity())]
+    }
+    /// Returns the coordinates of the cell within its face.
+    pub fn face_coords(&self, id: Index) -> Coord {
+        let local = id.rem(self.face_capacity());
+        Coord::new((local % *self.size).into(), (local / *self.size).into())
+    }
+    /// Returns the id of the cell at `coord` within the given face.
+    pub fn id_on(&self, face: Face, coord: &Coord) -> Index {
+        coord.id(self.size) + self.face_capacity().mul(face.index()).into()
+    }
+    pub fn render_options<'f, 'o, 'g>(
+        &'g self,
+        opts: &'o BasicOpts<'f>,
+    ) -> RenderGrid<'f, 'o, 'g, CubeGrid> {
+        RenderGrid::with_options(self, opts)
+    }
+    pub fn render_defaults<'f, 'o, 'g>(&'g self) -> RenderGrid<'f, 'o, 'g, CubeGrid> {
+        RenderGrid::new(self)
+    }
+    /// Finds the neighbor of a cell, folding over the edge of the cube when the neighbor is on
+    /// another face.
+    fn step(&self, id: Index, dir: &Cardinal) -> Index {
+        let n = *self.size as i64;
+        let face = self.face(id);
+        let coord = self.face_coords(id);
+        let (x, y) = (*coord.x() as i64, *coord.y() as i64);
+        let (dx, dy) = dir.map(|| (0, -1), || (1, 0), || (0, 1), || (-1, 0));
+        let (nx, ny) = (x + dx, y + dy);
+        if (0..n).contains(&nx) && (0..n).contains(&ny) {
+            return self.id_on(
+                face,
+                &Coord::new((nx as usize).into(), (ny as usize).into()),
+            );
+        }
+        // cell centers in doubled coordinates, so every center lies on the integer lattice
+        let (normal, right, down) = face.axes();
+        let center: Vec<i64> = (0..3)
+            .map(|i| normal[i] * n + right[i] * (2 * x + 1 - n) + down[i] * (2 * y + 1 - n))
+            .collect();
+        let dir: Vec<i64> = (0..3).map(|i| right[i] * dx + down[i] * dy).collect();
+        // move onto the shared edge, then one half cell down the adjoining face
+        let target: Vec<i64> = (0..3).map(|i| center[i] + dir[i] - normal[i]).collect();
+        let next = Face::from_normal([dir[0], dir[1], dir[2]]);
+        let (_, right, down) = next.axes();
+        let dot = |v: [i64; 3]| (0..3).map(|i| target[i] * v[i]).sum::<i64>();
+        let nx = (dot(right) + n - 1) / 2;
+        let ny = (dot(down) + n - 1) / 2;
+        self.id_on(
+            next,
+            &Coord::new((nx as usize).into(), (ny as usize).into()),
+        )
+    }
+}
+impl Grid for CubeGrid {}
+
+impl GridProps for CubeGrid {
+    type C = SqCell;
+    fn setup(size: usize) -> Self {
+        Self::new(size)
+    }
+    fn capacity(&self) -> Capacity {
+        self.face_capacity().mul(6usize).into()
+    }
+    fn cells(&self) -> &Vec<<Self as GridProps>::C> {
+        &self.cells
+    }
+}
+impl std::ops::Index<usize> for CubeGrid {
+    type Output = SqCell;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.cells[index]
+    }
+}
+impl CardinalGrid for CubeGrid {
+    /// The size of one face.
+    fn row_size(&self) -> RowSize {
+        self.size
+    }
+    fn col_size(&self) -> ColSize {
+        self.size
+    }
+    fn dimensions(&self) -> (RowSize, ColSize) {
+        (self.row_size(), self.row_size())
+    }
+    fn has_boundary_north(&self, _id: Index) -> bool {
+        false
+    }
+    fn has_boundary_east(&self, _id: Index) -> bool {
+        false
+    }
+    fn has_boundary_south(&self, _id: Index) -> bool {
+        false
+    }
+    fn has_boundary_west(&self, _id: Index) -> bool {
+        false
+    }
+    fn is_edge_north(&self, id: Index) -> bool {
+        *self.face_coords(id).y() == 0
+    }
+    fn is_edge_east(&self, id: Index) -> bool {
+        *self.face_coords(id).x() == *self.size - 1
+    }
+    fn is_edge_south(&self, id: Index) -> bool {
+        *self.face_coords(id).y() == *self.size - 1
+    }
+    fn is_edge_west(&self, id: Index) -> bool {
+        *self.face_coords(id).x() == 0
+    }
+    fn calc_north(&self, id: Index) -> Option<Index> {
+        Some(self.step(id, &Cardinal::N))
+    }
+    fn calc_east(&self, id: Index) -> Option<Index> {
+        Some(self.step(id, &Cardinal::E))
+    }
+    fn calc_south(&self, id: Index) -> Option<Index> {
+        Some(self.step(id, &Cardinal::S))
+    }
+    fn calc_west(&self, id: Index) -> Option<Index> {
+        Some(self.step(id, &Cardinal::W))
+    }
+}
+impl CoordLookup for CubeGrid {
+    /// Returns the id of the cell at `coord` in the unfolded net.
+    fn get_id(&self, coord: &Coord) -> Index {
+        self.try_get_id(coord).unwrap()
+    }
+    fn try_get_id(&self, coord: &Coord) -> Result<Index, OutOfBoundsCoordError> {
+        let (x, y) = (*coord.x(), *coord.y());
+        let size = *self.size;
+        Face::ALL
+            .iter()
+            .find(|f| f.net_position() == (x / size, y / size))
+            .map(|f| self.id_on(*f, &Coord::new((x % size).into(), (y % size).into())))
+            .ok_or_else(|| OutOfBoundsCoordError::new(*coord))
+    }
+    /// Returns the coordinates of the cell in the unfolded net.
+    fn get_coords(&self, id: Index) -> Coord {
+        let (col, row) = self.face(id).net_position();
+        let local = self.face_coords(id);
+        Coord::new(
+            local.x() + (col * *self.size).into(),
+            local.y() + (row * *self.size).into(),
+        )
+    }
+    fn try_get_coords(&self, id: Index) -> Result<Coord, OutOfBoundsError> {
+        if id.lt(self.capacity()) {
+            Ok(self.get_coords(id))
+        } else {
+            Err(OutOfBoundsError::new(id))
+        }
+    }
+}
+
+impl Renderable for CubeGrid {
+    type B = UnsignedIntBlock;
+    fn draw_joint(&self, id: Index, block: &Self::B, image: &mut RgbaImage, opts: &BasicOpts) {
+        draw_sq_joint(self, id, block, image, opts)
+    }
+    fn draw_block_outline(
+        &self,
+        id: Index,
+        block: &Self::B,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        draw_sq_outline(self, id, block, image, opts)
+    }
+    fn fill_block_bg(
+        &self,
+        _id: Index,
+        block: &Self::B,
+        color: &Rgba<u8>,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        fill_sq_block_bg(block, color, image, opts)
+    }
+    fn draw_block_text(
+        &self,
+        _id: Index,
+        block: &Self::B,
+        text: &str,
+        color: &Rgba<u8>,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        draw_sq_block_text(block, text, color, image, opts)
+    }
+    /// The net is four faces wide and three faces tall.
+    fn image_dimensions(&self, opts: &BasicOpts) -> (u32, u32) {
+        let frame = opts.frame_size();
+        let border = opts.border_width();
+        let face = self.size.mul(opts.block_size() + border) as u32;
+        (
+            frame + border + 4 * face + frame,
+            frame + border + 3 * face + frame,
+        )
+    }
+    /// Takes the rows of the net in the band from each face that has any of them.
+    fn band_cells(&self, top: u32, bottom: u32, opts: &BasicOpts) -> Vec<Range<usize>> {
+        let size = *self.size;
+        let cap = *self.face_capacity();
+        let rows = sq_band_rows(top, bottom, opts);
+        Face::ALL
+            .iter()
+            .filter_map(|face| {
+                let first = face.net_position().1 * size;
+                let start = rows.start.saturating_sub(first).min(size);
+                let end = rows.end.saturating_sub(first).min(size);
+                let offset = face.index() * cap;
+                (start < end).then(|| offset + start * size..offset + end * size)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Cell;
+    use crate::render::Renderer;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn cube_neighbors() {
+        let grid = CubeGrid::new(3);
+        let at = |face, x: usize, y: usize| grid.id_on(face, &Coord::new(x.into(), y.into()));
+        // edges that touch in the net
+        assert_eq!(
+            grid.neighbor(at(Face::Front, 0, 0), &Cardinal::N),
+            Some(at(Face::Up, 0, 2))
+        );
+        assert_eq!(
+            grid.neighbor(at(Face::Front, 2, 1), &Cardinal::E),
+            Some(at(Face::Right, 0, 1))
+        );
+        assert_eq!(
+            grid.neighbor(at(Face::Left, 0, 1), &Cardinal::W),
+            Some(at(Face::Back, 2, 1))
+        );
+        // edges that only meet once folded
+        assert_eq!(
+            grid.neighbor(at(Face::Up, 0, 0), &Cardinal::N),
+            Some(at(Face::Back, 2, 0))
+        );
+        assert_eq!(
+            grid.neighbor(at(Face::Up, 0, 1), &Cardinal::W),
+            Some(at(Face::Left, 1, 0))
+        );
+        assert_eq!(
+            grid.neighbor(at(Face::Down, 2, 0), &Cardinal::E),
+            Some(at(Face::Right, 0, 2))
+        );
+        assert_eq!(
+            grid.neighbor(at(Face::Down, 0, 2), &Cardinal::S),
+            Some(at(Face::Back, 2, 2))
+        );
+        for id in (0..*grid.capacity()).map(Index::from) {
+            let cell = grid.lookup(id);
+            assert_eq!(cell.neighbor_ids().len(), 4);
+            for n in cell.neighbor_ids().iter() {
+                assert!(grid.lookup(*n).neighbor_ids().contains(&id));
+            }
+            assert_eq!(grid.get_id(&grid.get_coords(id)), id);
+        }
+    }
+    #[test]
+    fn cube_net() -> Result<(), image::ImageError> {
+        let mut rng = SplitMix64::seed_from_u64(29);
+        let grid = CubeGrid::wilsons(4, &mut rng);
+        let dist = grid.distances(Index::zero());
+        assert_eq!(dist.map_ref().len(), *grid.capacity());
+        grid.render_defaults()
+            .save_render(std::path::Path::new("cube.png"))
+    }
+}
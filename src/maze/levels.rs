@@ -0,0 +1,298 @@
+use crate::error::*;
+use crate::maze::sq::SqCell;
+use crate::maze::sq::{
+    draw_sq_block_text, draw_sq_joint, draw_sq_outline, fill_sq_block_bg, sq_band_cells,
+};
+use crate::maze::{CardinalGrid, Cell, CoordLookup, Grid, GridProps};
+use crate::render::blocks::UnsignedIntBlock;
+use crate::render::renderers::RenderGrid;
+use crate::render::{BasicOpts, Renderable};
+use crate::util::*;
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::draw_line_segment_mut;
+use std::ops::Range;
+
+/// A 3D grid made of square levels stacked on top of each other.  Besides its neighbors on the
+/// same level, each cell neighbors the cells directly above and below it.
+///
+/// Cells are stored level by level, so the cell at `coord` on `level` has the id
+/// `level * size * size + coord.id(size)`.  [`CoordLookup`] works with coordinates on a level;
+/// use [`LevelGrid::level`] to find which level a cell is on.
+///
+/// [`GridProps::setup`] creates a cube with as many levels as `size`; use [`LevelGrid::new`] and
+/// one of the `carve_` methods on [`Grid`] to generate mazes with a different number of levels.
+#[derive(Clone, Debug)]
+pub struct LevelGrid {
+    size: RowSize,
+    levels: usize,
+    cells: Vec<SqCell>,
+}
+impl LevelGrid {
+    /// Creates `levels` levels of `size`×`size` cells.
+    ///
+    /// Panics if `levels` is zero.
+    pub fn new(size: usize, levels: usize) -> Self {
+        assert!(levels >= 1, "a level grid needs at least one level");
+        let mut grid = Self {
+            size: size.into(),
+            levels,
+            cells: Vec::with_capacity(size * size * levels),
+        };
+        for id in (0..size * size * levels).map(Index::from) {
+            let mut neighbors = Vec::<Index>::new();
+            for d in CardinalIter::iter() {
+                if let Some(neighbor) = grid.calc_dir(id, &d) {
+                    neighbors.push(neighbor);
+                }
+            }
+            neighbors.extend(grid.calc_up(id));
+            neighbors.extend(grid.calc_down(id));
+            grid.cells.push(SqCell::new(id, neighbors));
+        }
+        grid
+    }
+    pub fn levels(&self) -> usize {
+        self.levels
+    }
+    /// Number of cells on each level.
+    pub fn level_capacity(&self) -> Capacity {
+        self.size.cap()
+    }
+    /// Returns the level the cell is on, starting from `0` for the bottom level.
+    pub fn level(&self, id: Index) -> usize {
+        id.div(self.level_capacity())
+    }
+    /// Returns the id of the cell at `coord` on the given level.
+    pub fn id_at(&self, level: usize, coord: &Coord) -> Index {
+        self.get_id(coord) + self.level_capacity().mul(level).into()
+    }
+    pub fn calc_up(&self, id: Index) -> Option<Index> {
+        match self.level(id) + 1 < self.levels {
+            true => Some(id.plus(self.level_capacity()).into()),
+            false => None,
+        }
+    }
+    pub fn calc_down(&self, id: Index) -> Option<Index> {
+        match self.level(id) > 0 {
+            true => Some(id.minus(self.level_capacity()).into()),
+            false => None,
+        }
+    }
+    pub fn has_up_link(&self, id: Index) -> bool {
+        matches!(self.calc_up(id), Some(up) if self.lookup(id).has_link(up))
+    }
+    pub fn has_down_link(&self, id: Index) -> bool {
+        matches!(self.calc_down(id), Some(down) if self.lookup(id).has_link(down))
+    }
+    pub fn render_options<'f, 'o, 'g>(
+        &'g self,
+        opts: &'o BasicOpts<'f>,
+    ) -> RenderGrid<'f, 'o, 'g, LevelGrid> {
+        RenderGrid::with_options(self, opts)
+    }
+    pub fn render_defaults<'f, 'o, 'g>(&'g self) -> RenderGrid<'f, 'o, 'g, LevelGrid> {
+        RenderGrid::new(self)
+    }
+    /// The width of one rendered level, not including the frame.
+    fn level_width(&self, opts: &BasicOpts) -> u32 {
+        opts.border_width() + self.size.mul(opts.block_size() + opts.border_width()) as u32
+    }
+    /// Levels are drawn side by side from left to right with `frame_size` pixels between them.
+    pub(in crate) fn block_coords(&self, id: Index, opts: &BasicOpts) -> UnsignedIntBlock {
+        let mut block = UnsignedIntBlock::new(self, id, opts);
+        let offset = self.level(id) as u32 * (self.level_width(opts) + opts.frame_size());
+        block.x1 += offset;
+        block.x2 += offset;
+        block
+    }
+    /// Marks stairs with a chevron in the top right corner of the block for links to the level
+    /// above and in the bottom right corner for links to the level below.
+    fn draw_stairs(
+        &self,
+        id: Index,
+        block: &UnsignedIntBlock,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        let width = (opts.block_size() / 5) as f32;
+        let height = width / 2.0;
+        let pad = opts.block_padding() as f32 + opts.joint_size() as f32;
+        let right = (block.x1 + opts.block_size()) as f32 - pad;
+        let left = right - width;
+        let color = *opts.fg_color();
+        if self.has_up_link(id) {
+            let top = block.y1 as f32 + pad;
+            draw_line_segment_mut(image, (left, top + height), (left + height, top), color);
+            draw_line_segment_mut(image, (left + height, top), (right, top + height), color);
+        }
+        if self.has_down_link(id) {
+            let bottom = (block.y1 + opts.block_size()) as f32 - pad;
+            draw_line_segment_mut(
+                image,
+                (left, bottom - height),
+                (left + height, bottom),
+                color,
+            );
+            draw_line_segment_mut(
+                image,
+                (left + height, bottom),
+                (right, bottom - height),
+                color,
+            );
+        }
+    }
+}
+impl Grid for LevelGrid {}
+
+impl GridProps for LevelGrid {
+    type C = SqCell;
+    fn setup(size: usize) -> Self {
+        Self::new(size, size)
+    }
+    fn capacity(&self) -> Capacity {
+        self.level_capacity().mul(self.levels).into()
+    }
+    fn cells(&self) -> &Vec<<Self as GridProps>::C> {
+        &self.cells
+    }
+}
+impl std::ops::Index<usize> for LevelGrid {
+    type Output = SqCell;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.cells[index]
+    }
+}
+impl CardinalGrid for LevelGrid {
+    fn row_size(&self) -> RowSize {
+        self.size
+    }
+    fn col_size(&self) -> ColSize {
+        self.size
+    }
+    fn dimensions(&self) -> (RowSize, ColSize) {
+        (self.row_size(), self.row_size())
+    }
+    fn is_edge_north(&self, id: Index) -> bool {
+        id.rem(self.level_capacity()) < *self.row_size()
+    }
+    fn is_edge_south(&self, id: Index) -> bool {
+        id.rem(self.level_capacity()) / *self.row_size() == *self.row_size() - 1
+    }
+}
+impl CoordLookup for LevelGrid {
+    /// Returns the id of the cell at `coord` on the bottom level.
+    fn get_id(&self, coord: &Coord) -> Index {
+        coord.y() * self.size.into() + coord.x()
+    }
+    fn try_get_id(&self, coord: &Coord) -> Result<Index, OutOfBoundsCoordError> {
+        if coord.x().lt(self.size) && coord.y().lt(self.size) {
+            Ok(self.get_id(coord))
+        } else {
+            Err(OutOfBoundsCoordError::new(*coord))
+        }
+    }
+    /// Returns the coordinates of the cell on its level.
+    fn get_coords(&self, id: Index) -> Coord {
+        let local = id.rem(self.level_capacity());
+        Coord::new((local % *self.size).into(), (local / *self.size).into())
+    }
+    fn try_get_coords(&self, id: Index) -> Result<Coord, OutOfBoundsError> {
+        if id.lt(self.capacity()) {
+            Ok(self.get_coords(id))
+        } else {
+            Err(OutOfBoundsError::new(id))
+        }
+    }
+}
+
+impl Renderable for LevelGrid {
+    type B = UnsignedIntBlock;
+    fn draw_joint(&self, id: Index, block: &Self::B, image: &mut RgbaImage, opts: &BasicOpts) {
+        draw_sq_joint(self, id, block, image, opts)
+    }
+    fn draw_block_outline(
+        &self,
+        id: Index,
+        block: &Self::B,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        draw_sq_outline(self, id, block, image, opts);
+        self.draw_stairs(id, block, image, opts);
+    }
+    fn fill_block_bg(
+        &self,
+        _id: Index,
+        block: &Self::B,
+        color: &Rgba<u8>,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        fill_sq_block_bg(block, color, image, opts)
+    }
+    fn draw_block_text(
+        &self,
+        _id: Index,
+        block: &Self::B,
+        text: &str,
+        color: &Rgba<u8>,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        draw_sq_block_text(block, text, color, image, opts)
+    }
+    fn image_dimensions(&self, opts: &BasicOpts) -> (u32, u32) {
+        let frame = opts.frame_size();
+        let levels = self.levels as u32;
+        let x = frame + levels * self.level_width(opts) + (levels - 1) * frame + frame;
+        let y = frame + self.level_width(opts) + frame;
+        (x, y)
+    }
+    /// The levels are side by side, so the band holds the same rows of every level.
+    fn band_cells(&self, top: u32, bottom: u32, opts: &BasicOpts) -> Vec<Range<usize>> {
+        let cap = *self.level_capacity();
+        let rows = sq_band_cells(self, top, bottom, opts);
+        (0..self.levels)
+            .map(|level| rows.start + level * cap..rows.end + level * cap)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Renderer;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn level_neighbors() {
+        let grid = LevelGrid::new(3, 2);
+        assert_eq!(grid.capacity(), 18.into());
+        assert_eq!(grid.level(10.into()), 1);
+        assert_eq!(grid.calc_up(1.into()), Some(10.into()));
+        assert_eq!(grid.calc_down(10.into()), Some(1.into()));
+        assert_eq!(grid.calc_up(10.into()), None);
+        assert_eq!(grid.neighbor(9.into(), &Cardinal::N), None);
+        assert_eq!(grid.neighbor(12.into(), &Cardinal::N), Some(9.into()));
+        assert_eq!(grid.neighbor(15.into(), &Cardinal::S), None);
+        assert_eq!(grid.lookup(13.into()).neighbor_ids().len(), 5);
+        assert_eq!(grid.id_at(1, &Coord::new(1.into(), 1.into())), 13.into());
+    }
+    #[test]
+    #[should_panic(expected = "at least one level")]
+    fn no_levels() {
+        LevelGrid::new(3, 0);
+    }
+    #[test]
+    fn level_distances() -> Result<(), image::ImageError> {
+        let mut rng = SplitMix64::seed_from_u64(3);
+        let grid = LevelGrid::new(5, 3);
+        grid.carve_wilsons(&mut rng);
+        let dist = grid.distances(Index::zero());
+        assert_eq!(dist.map_ref().len(), *grid.capacity());
+        let path = grid.longest_path(Index::zero());
+        assert!(path.len() > 1);
+        grid.render_defaults()
+            .save_render(std::path::Path::new("levels.png"))
+    }
+}
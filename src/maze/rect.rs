@@ -4,15 +4,48 @@ use crate::maze::{Cell, Grid};
 use crate::trans::major::{Major, RowMajor};
 use crate::trans::*;
 use crate::util::*;
+use parse_display::Display;
 use rand::seq::SliceRandom;
 use rand::Rng;
 
+/// Describes how the edges of a [`CardinalGrid`] connect to each other.
+#[derive(Clone, Copy, Debug, Default, Display, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Topology {
+    /// A flat grid; every edge is a boundary.
+    #[default]
+    Plane,
+    /// The east edge neighbors the west edge.
+    Cylinder,
+    /// The east edge neighbors the west edge and the north edge neighbors the south edge.
+    Torus,
+}
+impl Topology {
+    /// Whether the east and west edges are neighbors.
+    pub fn wraps_x(&self) -> bool {
+        matches!(self, Self::Cylinder | Self::Torus)
+    }
+    /// Whether the north and south edges are neighbors.
+    pub fn wraps_y(&self) -> bool {
+        matches!(self, Self::Torus)
+    }
+    /// Whether a square grid of `size`×`size` cells can have this topology.  A wrapping axis
+    /// needs at least three cells, or a cell would neighbor itself or the same cell twice.
+    pub fn fits(&self, size: usize) -> bool {
+        !self.wraps_x() || size >= 3
+    }
+}
+
 /// Describes grids that can be navigated using cardinal directions.
 /// This allows starting iteration at an ordinal direction.
 pub trait CardinalGrid: Grid {
     fn row_size(&self) -> RowSize;
     fn col_size(&self) -> ColSize;
     fn dimensions(&self) -> (RowSize, ColSize);
+    /// How the edges of the grid connect.  Boundaries only exist on edges that do not wrap around.
+    fn topology(&self) -> Topology {
+        Topology::Plane
+    }
     fn major_order_fn<M: Major>(ordinal: Ordinal) -> fn(Visit, RowSize, ColSize, M) -> Index {
         match ordinal {
             Ordinal::Nw => Self::calc_major_order_nw,
@@ -33,7 +66,7 @@ pub trait CardinalGrid: Grid {
     fn calc_major_order_sw<M: Major>(id: Visit, rows: RowSize, _cols: ColSize, order: M) -> Index {
         Ordinal::Sw.major_order_index(id, rows, order)
     }
-    fn nw(&self) -> Iter<Self, Nw<RowMajor>>
+    fn nw(&self) -> Iter<'_, Self, Nw<RowMajor>>
     where
         Self: Sized + Grid + CardinalGrid,
     {
@@ -41,7 +74,7 @@ pub trait CardinalGrid: Grid {
         let m = Self::major_order_fn(Ordinal::Nw);
         Iter::new(self, Nw::new(rows, cols, m))
     }
-    fn ne(&self) -> Iter<Self, Ne<RowMajor>>
+    fn ne(&self) -> Iter<'_, Self, Ne<RowMajor>>
     where
         Self: Sized + Grid + CardinalGrid,
     {
@@ -49,7 +82,7 @@ pub trait CardinalGrid: Grid {
         let m = Self::major_order_fn(Ordinal::Ne);
         Iter::new(self, Ne::new(rows, cols, m))
     }
-    fn se(&self) -> Iter<Self, Se<RowMajor>>
+    fn se(&self) -> Iter<'_, Self, Se<RowMajor>>
     where
         Self: Sized + Grid + CardinalGrid,
     {
@@ -57,7 +90,7 @@ pub trait CardinalGrid: Grid {
         let m = Self::major_order_fn(Ordinal::Se);
         Iter::new(self, Se::new(rows, cols, m))
     }
-    fn sw(&self) -> Iter<Self, Sw<RowMajor>>
+    fn sw(&self) -> Iter<'_, Self, Sw<RowMajor>>
     where
         Self: Sized + Grid + CardinalGrid,
     {
@@ -74,20 +107,76 @@ pub trait CardinalGrid: Grid {
         }
     }
     fn has_boundary_north(&self, id: Index) -> bool {
-        id.lt(self.row_size())
+        self.is_edge_north(id) && !self.topology().wraps_y()
     }
     fn has_boundary_east(&self, id: Index) -> bool {
-        id.rem(self.row_size()) == *self.row_size() - 1
+        self.is_edge_east(id) && !self.topology().wraps_x()
     }
     fn has_boundary_south(&self, id: Index) -> bool {
-        id.div(self.row_size()) == *self.row_size() - 1
+        self.is_edge_south(id) && !self.topology().wraps_y()
     }
     fn has_boundary_west(&self, id: Index) -> bool {
+        self.is_edge_west(id) && !self.topology().wraps_x()
+    }
+    /// Whether the cell lies on the edge of the grid in the given direction, regardless of
+    /// whether that edge wraps around.
+    fn is_edge(&self, id: Index, dir: Cardinal) -> bool {
+        match dir {
+            Cardinal::N => self.is_edge_north(id),
+            Cardinal::E => self.is_edge_east(id),
+            Cardinal::S => self.is_edge_south(id),
+            Cardinal::W => self.is_edge_west(id),
+        }
+    }
+    fn is_edge_north(&self, id: Index) -> bool {
+        id.lt(self.row_size())
+    }
+    fn is_edge_east(&self, id: Index) -> bool {
+        id.rem(self.row_size()) == *self.row_size() - 1
+    }
+    fn is_edge_south(&self, id: Index) -> bool {
+        id.div(self.row_size()) == *self.row_size() - 1
+    }
+    fn is_edge_west(&self, id: Index) -> bool {
         id.rem(self.row_size()) == 0
     }
+    /// Whether moving from `id` in the given direction wraps around to the opposite edge.
+    fn wraps(&self, id: Index, dir: Cardinal) -> bool {
+        self.is_edge(id, dir) && !self.has_boundary(id, dir)
+    }
     fn find_boundary(&self, id: Index) -> Option<Cardinal> {
         Cardinal::iter().find(|d| self.has_boundary(id, *d))
     }
+    /// Openings in the boundary, such as the entrance and exit of the maze.  Renderers leave a
+    /// gap in the outer wall for each of them.  Grids that do not support openings have none.
+    fn openings(&self) -> &[(Index, Cardinal)] {
+        &[]
+    }
+    fn is_open(&self, id: Index, dir: Cardinal) -> bool {
+        self.openings().contains(&(id, dir))
+    }
+    /// Finds two cells on the boundary that are as far apart as possible, which makes them a
+    /// good entrance and exit.  Uses two sweeps of [`Grid::distances`], which finds the farthest
+    /// pair in perfect mazes and a close approximation otherwise.
+    fn farthest_boundary_cells(&self) -> Option<(Index, Index)>
+    where
+        Self: Sized,
+    {
+        let boundary: Vec<Index> = (0..*self.capacity())
+            .map(Index::from)
+            .filter(|id| self.find_boundary(*id).is_some())
+            .collect();
+        let farthest = |from: Index| {
+            let dist = self.distances(from);
+            boundary
+                .iter()
+                .filter_map(|id| dist.get(*id).map(|d| (d, *id)))
+                .max()
+                .map(|(_, id)| id)
+        };
+        let first = farthest(*boundary.first()?)?;
+        Some((first, farthest(first)?))
+    }
     fn dir_from(&self, from: Index, to: Index) -> Option<Cardinal> {
         for d in Cardinal::iter() {
             if matches!(self.neighbor(from, &d), Some(n) if n == to) {
@@ -107,7 +196,11 @@ pub trait CardinalGrid: Grid {
     fn neighbor(&self, id: Index, d: &Cardinal) -> Option<Index> {
         self.calc_dir(id, d)
     }
-    fn link_neighbor(&self, id: Index, d: &Cardinal) -> Result<(), NotNeighborsError<Cardinal>> {
+    fn link_neighbor(
+        &self,
+        id: Index,
+        d: &Cardinal,
+    ) -> Result<(), NotNeighborsError<'_, Cardinal>> {
         if let Some(n) = self.neighbor(id, d) {
             self.link(id, n).unwrap();
             Ok(())
@@ -119,7 +212,11 @@ pub trait CardinalGrid: Grid {
             ))
         }
     }
-    fn unlink_neighbor(&self, id: Index, d: &Cardinal) -> Result<(), NotNeighborsError<Cardinal>> {
+    fn unlink_neighbor(
+        &self,
+        id: Index,
+        d: &Cardinal,
+    ) -> Result<(), NotNeighborsError<'_, Cardinal>> {
         if let Some(n) = self.neighbor(id, d) {
             self.unlink(id, n).unwrap();
             Ok(())
@@ -140,27 +237,35 @@ pub trait CardinalGrid: Grid {
         }
     }
     fn calc_north(&self, id: Index) -> Option<Index> {
-        match self.has_boundary_north(id) {
-            true => None,
-            false => Some(id.minus(self.row_size()).into()),
+        match (self.has_boundary_north(id), self.is_edge_north(id)) {
+            (true, _) => None,
+            (false, true) => {
+                Some((id.plus(self.row_size().mul(self.col_size())) - *self.row_size()).into())
+            }
+            (false, false) => Some(id.minus(self.row_size()).into()),
         }
     }
     fn calc_east(&self, id: Index) -> Option<Index> {
-        match self.has_boundary_east(id) {
-            true => None,
-            false => Some(id.plus(1usize).into()),
+        match (self.has_boundary_east(id), self.is_edge_east(id)) {
+            (true, _) => None,
+            (false, true) => Some((id.plus(1usize) - *self.row_size()).into()),
+            (false, false) => Some(id.plus(1usize).into()),
         }
     }
     fn calc_south(&self, id: Index) -> Option<Index> {
-        match self.has_boundary_south(id) {
-            true => None,
-            false => Some(id.plus(self.row_size()).into()),
+        match (self.has_boundary_south(id), self.is_edge_south(id)) {
+            (true, _) => None,
+            (false, true) => {
+                Some((id.plus(self.row_size()) % self.row_size().mul(self.col_size())).into())
+            }
+            (false, false) => Some(id.plus(self.row_size()).into()),
         }
     }
     fn calc_west(&self, id: Index) -> Option<Index> {
-        match self.has_boundary_west(id) {
-            true => None,
-            false => Some(id.minus(1usize).into()),
+        match (self.has_boundary_west(id), self.is_edge_west(id)) {
+            (true, _) => None,
+            (false, true) => Some((id.plus(self.row_size()) - 1).into()),
+            (false, false) => Some(id.minus(1usize).into()),
         }
     }
     fn corner_id(&self, dir: Ordinal) -> Index {
@@ -1,25 +1,50 @@
-use crate::maze::Cell;
+use crate::maze::{Cell, LinkStore};
 use crate::util::*;
 use parse_display::Display;
 use std::cell::RefCell;
-#[derive(Clone, Debug, Display)]
+/// A square cell, generic over how its links are stored (see [`LinkStore`]).
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[display("Cell {id}")]
 // todo: impl PartialEq, PartialOrd, Add/Sub/Mul/Div/Rem/AddAssign/SubAssign/MulAssign/DivAssign/Deref
-pub struct SqCell {
+pub struct SqCell<L = RefCell<Vec<Index>>> {
     id: Index,
-    links: RefCell<Vec<Index>>,
+    links: L,
     neighbors: Vec<Index>,
 }
-impl SqCell {
-    pub(in crate::maze::sq) fn new(id: Index, neighbors: Vec<Index>) -> Self {
+impl<L: LinkStore> SqCell<L> {
+    pub(in crate::maze) fn new(id: Index, neighbors: Vec<Index>) -> Self {
         Self {
             id,
-            links: RefCell::from(Vec::new()),
+            links: L::default(),
             neighbors,
         }
     }
+    /// Copies the cell, moving its links into another kind of [`LinkStore`].
+    pub(in crate::maze) fn convert<M: LinkStore>(&self) -> SqCell<M> {
+        let links = M::default();
+        for id in self.links.borrow().iter() {
+            links.push(*id);
+        }
+        SqCell {
+            id: self.id,
+            links,
+            neighbors: self.neighbors.clone(),
+        }
+    }
 }
-impl Cell for SqCell {
+/// Copies the links into a new store, as [`RwLock`](std::sync::RwLock) is not `Clone`.
+impl<L: LinkStore> Clone for SqCell<L> {
+    fn clone(&self) -> Self {
+        self.convert()
+    }
+}
+impl<L: LinkStore> Cell for SqCell<L> {
+    type Links = L;
+    type Neighbors<'a>
+        = &'a [Index]
+    where
+        L: 'a;
     fn id(&self) -> Index {
         self.id
     }
@@ -27,14 +52,12 @@ impl Cell for SqCell {
         &self.neighbors
     }
     fn unchecked_link(&self, with: Index) {
-        self.links.borrow_mut().push(with);
+        self.links.push(with);
     }
-    fn links(&self) -> &RefCell<Vec<Index>> {
+    fn links(&self) -> &L {
         &self.links
     }
     fn unchecked_unlink(&self, with: Index) {
-        if let Some(pos) = self.links.borrow().iter().position(|n| *n == with) {
-            self.links.borrow_mut().remove(pos);
-        }
+        self.links.remove(with);
     }
 }
@@ -0,0 +1,371 @@
+//! A square grid for very large mazes, storing each cell's links as direction bits.
+//!
+//! A [`SqGrid`](super::SqGrid) cell keeps vectors of its neighbors and links, costing around a
+//! hundred bytes per cell.  A [`CompactSqCell`] only stores its id, the row size and one byte of
+//! bits: which directions have a neighbor (found with [`CardinalGrid::calc_dir`] when the grid is
+//! made) and which of them are linked.  Neighbor and link ids are computed from these bits when
+//! asked for, so a 10,000×10,000 maze takes a little over a gigabyte.  Openings in the boundary
+//! are kept in a list on the grid, like on a [`SqGrid`](super::SqGrid).
+//!
+//! Grids are limited to `u32::MAX` cells.
+
+use crate::error::*;
+use crate::maze::sq::{
+    draw_sq_block_text, draw_sq_joint, draw_sq_outline, fill_sq_block_bg, sq_band_cells,
+    sq_image_dimensions,
+};
+use crate::maze::{CardinalGrid, Cell, CoordLookup, Grid, GridProps, LinkStore, Topology};
+use crate::render::blocks::UnsignedIntBlock;
+use crate::render::renderers::RenderGrid;
+use crate::render::{BasicOpts, Renderable};
+use crate::util::*;
+use image::{Rgba, RgbaImage};
+use std::iter::FromIterator;
+use std::ops::Deref;
+use std::ops::Range;
+
+/// Neighbor bits are stored in the high half of a cell's bits and link bits in the low half.
+const NEIGHBOR_SHIFT: u8 = 4;
+
+fn bit(d: Cardinal) -> u8 {
+    match d {
+        Cardinal::N => 1,
+        Cardinal::E => 2,
+        Cardinal::S => 4,
+        Cardinal::W => 8,
+    }
+}
+
+/// The ids of up to four neighbors of a [`CompactSqCell`], computed from its bits without
+/// allocating.  Dereferences to a slice of the ids, in the order N, E, S, W.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DirIds {
+    ids: [Index; 4],
+    len: u8,
+}
+impl FromIterator<Index> for DirIds {
+    /// Panics if there are more than four ids.
+    fn from_iter<I: IntoIterator<Item = Index>>(iter: I) -> Self {
+        let mut dirs = Self::default();
+        for id in iter {
+            dirs.ids[dirs.len as usize] = id;
+            dirs.len += 1;
+        }
+        dirs
+    }
+}
+impl Deref for DirIds {
+    type Target = [Index];
+    fn deref(&self) -> &[Index] {
+        &self.ids[..self.len as usize]
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CompactSqCell {
+    id: u32,
+    size: u32,
+    bits: std::cell::Cell<u8>,
+}
+impl CompactSqCell {
+    /// The neighbor in direction `d`, if the cell has one.
+    pub fn neighbor(&self, d: Cardinal) -> Option<Index> {
+        if self.bits.get() & bit(d) << NEIGHBOR_SHIFT == 0 {
+            return None;
+        }
+        let (id, size) = (self.id as usize, self.size as usize);
+        // neighbors on the other side of an edge are only present when the edge wraps around
+        let n = match d {
+            Cardinal::N if id < size => id + size * size - size,
+            Cardinal::N => id - size,
+            Cardinal::E if id % size == size - 1 => id + 1 - size,
+            Cardinal::E => id + 1,
+            Cardinal::S => (id + size) % (size * size),
+            Cardinal::W if id % size == 0 => id + size - 1,
+            Cardinal::W => id - 1,
+        };
+        Some(n.into())
+    }
+    /// Whether the cell is linked to its neighbor in direction `d`.
+    pub fn has_dir_link(&self, d: Cardinal) -> bool {
+        self.bits.get() & bit(d) != 0
+    }
+    fn dir_to(&self, id: Index) -> Option<Cardinal> {
+        Cardinal::iter().find(|d| self.neighbor(*d) == Some(id))
+    }
+}
+impl Cell for CompactSqCell {
+    /// The cell stores its own links.
+    type Links = Self;
+    type Neighbors<'a> = DirIds;
+    fn id(&self) -> Index {
+        (self.id as usize).into()
+    }
+    fn neighbor_ids(&self) -> DirIds {
+        Cardinal::iter().filter_map(|d| self.neighbor(d)).collect()
+    }
+    /// Cells that are not neighbors can not be linked and are ignored.
+    fn unchecked_link(&self, with: Index) {
+        self.push(with);
+    }
+    fn links(&self) -> &Self {
+        self
+    }
+    fn unchecked_unlink(&self, with: Index) {
+        LinkStore::remove(self, with);
+    }
+}
+impl LinkStore for CompactSqCell {
+    type Ref<'a> = DirIds;
+    fn borrow(&self) -> DirIds {
+        Cardinal::iter()
+            .filter(|d| self.has_dir_link(*d))
+            .filter_map(|d| self.neighbor(d))
+            .collect()
+    }
+    fn push(&self, id: Index) {
+        if let Some(d) = self.dir_to(id) {
+            self.bits.set(self.bits.get() | bit(d));
+        }
+    }
+    fn remove(&self, id: Index) {
+        if let Some(d) = self.dir_to(id) {
+            self.bits.set(self.bits.get() & !bit(d));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CompactSqGrid {
+    size: RowSize,
+    cells: Vec<CompactSqCell>,
+    topology: Topology,
+    openings: Vec<(Index, Cardinal)>,
+}
+impl CompactSqGrid {
+    pub fn new(size: usize) -> Self {
+        Self::with_topology(size, Topology::Plane)
+    }
+    /// Creates a grid whose edges wrap around according to `topology`.
+    ///
+    /// Panics if the grid would have more than `u32::MAX` cells, or if `topology` wraps around
+    /// and `size` is below 3 (see [`Topology::fits`]).
+    pub fn with_topology(size: usize, topology: Topology) -> Self {
+        assert!(
+            topology.fits(size),
+            "a wrapping grid needs at least 3 cells per side"
+        );
+        assert!(
+            size.checked_mul(size)
+                .is_some_and(|c| c <= u32::MAX as usize),
+            "a compact grid holds at most u32::MAX cells"
+        );
+        let mut grid = Self {
+            size: size.into(),
+            cells: Vec::with_capacity(size * size),
+            topology,
+            openings: Vec::new(),
+        };
+        for id in (0..size * size).map(Index::from) {
+            let neighbors = Cardinal::iter()
+                .filter(|d| grid.calc_dir(id, d).is_some())
+                .fold(0, |bits, d| bits | bit(d));
+            grid.cells.push(CompactSqCell {
+                id: *id as u32,
+                size: size as u32,
+                bits: std::cell::Cell::new(neighbors << NEIGHBOR_SHIFT),
+            });
+        }
+        grid
+    }
+    /// Removes the outer wall of the cell in the given direction, e.g. to mark an entrance.
+    pub fn open_boundary(&mut self, id: Index, dir: Cardinal) -> Result<(), NoBoundaryError> {
+        if !self.has_boundary(id, dir) {
+            return Err(NoBoundaryError::new(id, dir));
+        }
+        if !self.is_open(id, dir) {
+            self.openings.push((id, dir));
+        }
+        Ok(())
+    }
+    /// Closes all openings made with [`CompactSqGrid::open_boundary`].
+    pub fn close_boundaries(&mut self) {
+        self.openings.clear();
+    }
+    /// Replaces any existing openings with an entrance and an exit on the boundary, in the same
+    /// way as [`SqGrid::place_entrances`](super::SqGridOf::place_entrances).
+    pub fn place_entrances(&mut self) -> Option<(Index, Index)> {
+        let (entrance, exit) = self.farthest_boundary_cells()?;
+        self.close_boundaries();
+        for id in [entrance, exit] {
+            let dir = self.find_boundary(id)?;
+            self.open_boundary(id, dir).ok()?;
+        }
+        Some((entrance, exit))
+    }
+    pub fn render_options<'f, 'o, 'g>(
+        &'g self,
+        opts: &'o BasicOpts<'f>,
+    ) -> RenderGrid<'f, 'o, 'g, Self> {
+        RenderGrid::with_options(self, opts)
+    }
+    pub fn render_defaults<'f, 'o, 'g>(&'g self) -> RenderGrid<'f, 'o, 'g, Self> {
+        RenderGrid::new(self)
+    }
+}
+impl Grid for CompactSqGrid {}
+impl GridProps for CompactSqGrid {
+    type C = CompactSqCell;
+    fn setup(size: usize) -> Self {
+        Self::new(size)
+    }
+    fn capacity(&self) -> Capacity {
+        self.size.cap()
+    }
+    fn cells(&self) -> &Vec<Self::C> {
+        &self.cells
+    }
+}
+impl CardinalGrid for CompactSqGrid {
+    fn row_size(&self) -> RowSize {
+        self.size
+    }
+    fn col_size(&self) -> ColSize {
+        self.size
+    }
+    fn dimensions(&self) -> (RowSize, ColSize) {
+        (self.row_size(), self.row_size())
+    }
+    fn topology(&self) -> Topology {
+        self.topology
+    }
+    fn has_dir_link(&self, id: Index, d: &Cardinal) -> bool {
+        self.get(id).is_some_and(|c| c.has_dir_link(*d))
+    }
+    fn openings(&self) -> &[(Index, Cardinal)] {
+        &self.openings
+    }
+}
+impl CoordLookup for CompactSqGrid {
+    fn get_id(&self, coord: &Coord) -> Index {
+        coord.y() * self.size.into() + coord.x()
+    }
+    fn try_get_id(&self, coord: &Coord) -> Result<Index, OutOfBoundsCoordError> {
+        match *coord.x() < *self.size && *coord.y() < *self.size {
+            true => Ok(self.get_id(coord)),
+            false => Err(OutOfBoundsCoordError::new(*coord)),
+        }
+    }
+    fn get_coords(&self, id: Index) -> Coord {
+        Coord::new(id.rem(self.size).into(), id.div(self.size).into())
+    }
+    fn try_get_coords(&self, id: Index) -> Result<Coord, OutOfBoundsError> {
+        match *id < *self.capacity() {
+            true => Ok(self.get_coords(id)),
+            false => Err(OutOfBoundsError::new(id)),
+        }
+    }
+}
+impl Renderable for CompactSqGrid {
+    type B = UnsignedIntBlock;
+    fn draw_joint(&self, id: Index, block: &Self::B, image: &mut RgbaImage, opts: &BasicOpts) {
+        draw_sq_joint(self, id, block, image, opts)
+    }
+    fn draw_block_outline(
+        &self,
+        id: Index,
+        block: &Self::B,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        draw_sq_outline(self, id, block, image, opts)
+    }
+    fn fill_block_bg(
+        &self,
+        _id: Index,
+        block: &Self::B,
+        color: &Rgba<u8>,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        fill_sq_block_bg(block, color, image, opts)
+    }
+    fn draw_block_text(
+        &self,
+        _id: Index,
+        block: &Self::B,
+        text: &str,
+        color: &Rgba<u8>,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        draw_sq_block_text(block, text, color, image, opts)
+    }
+    fn image_dimensions(&self, opts: &BasicOpts) -> (u32, u32) {
+        sq_image_dimensions(self, opts)
+    }
+    fn band_cells(&self, top: u32, bottom: u32, opts: &BasicOpts) -> Vec<Range<usize>> {
+        vec![sq_band_cells(self, top, bottom, opts)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::sq::SqGrid;
+    use crate::render::Renderer;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn compact_neighbors() {
+        assert!(std::mem::size_of::<CompactSqCell>() <= 12);
+        for topology in [Topology::Plane, Topology::Cylinder, Topology::Torus] {
+            let grid = CompactSqGrid::with_topology(5, topology);
+            let sq = SqGrid::with_topology(5, topology);
+            for (a, b) in grid.iter().zip(sq.iter()) {
+                assert_eq!(*a.neighbor_ids(), *b.neighbor_ids());
+                for d in Cardinal::iter() {
+                    assert_eq!(a.neighbor(d), grid.calc_dir(a.id(), &d));
+                }
+            }
+        }
+    }
+    #[test]
+    fn compact_maze() -> Result<(), image::ImageError> {
+        // the same seed carves the same maze as a regular grid
+        let grid = CompactSqGrid::wilsons(12, &mut SplitMix64::seed_from_u64(48));
+        let sq = SqGrid::wilsons(12, &mut SplitMix64::seed_from_u64(48));
+        for (a, b) in grid.iter().zip(sq.iter()) {
+            let (mut links, mut expected) =
+                (a.links().borrow().to_vec(), b.links().borrow().clone());
+            links.sort();
+            expected.sort();
+            assert_eq!(links, expected);
+        }
+        assert!(grid.validate().is_perfect());
+        let end: Index = 143.into();
+        assert_eq!(
+            grid.shortest_path(Index::zero(), end).get_ref(),
+            sq.shortest_path(Index::zero(), end).get_ref()
+        );
+        grid.unlink(0.into(), 1.into()).unwrap();
+        grid.unlink(0.into(), 12.into()).unwrap();
+        assert!(grid.lookup(0.into()).not_linked());
+        assert_eq!(
+            grid.render_defaults().render_rgba().dimensions(),
+            sq.render_defaults().render_rgba().dimensions()
+        );
+        grid.render_defaults()
+            .save_render(std::path::Path::new("compact.png"))
+    }
+    #[test]
+    fn compact_entrances() {
+        let mut grid = CompactSqGrid::wilsons(8, &mut SplitMix64::seed_from_u64(48));
+        let mut sq = SqGrid::wilsons(8, &mut SplitMix64::seed_from_u64(48));
+        assert_eq!(grid.place_entrances(), sq.place_entrances());
+        assert_eq!(grid.openings(), sq.openings());
+        assert!(grid.render_defaults().render_rgba() == sq.render_defaults().render_rgba());
+        grid.close_boundaries();
+        assert!(grid.openings().is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 pub mod cell;
+pub mod compact;
 use crate::error::*;
-use crate::maze::{CardinalGrid, Cell, CoordLookup, Grid, GridProps};
+use crate::maze::{CardinalGrid, CoordLookup, Grid, GridProps, LinkStore, Topology};
 use crate::render::blocks::{FloatBlock, UnsignedIntBlock};
 use crate::render::renderers::RenderGrid;
 use crate::render::{BasicOpts, Renderable};
@@ -10,22 +11,161 @@ pub use cell::SqCell;
 use image::{Rgba, RgbaImage};
 use imageproc::drawing::{draw_filled_rect_mut, draw_line_segment_mut, draw_text_mut};
 use imageproc::rect::Rect;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::sync::RwLock;
 
+/// A square grid using [`RefCell`] to store links, which is the fastest choice when the grid is
+/// only used from one thread.
+pub type SqGrid = SqGridOf<RefCell<Vec<Index>>>;
+/// A square grid that is `Send + Sync`, storing links in an [`RwLock`].  It can be shared between
+/// threads in an [`Arc`](std::sync::Arc) to be solved and rendered concurrently.
+///
+/// # Example
+/// ```
+/// use mazes::maze::sq::SyncSqGrid;
+/// use mazes::maze::Grid;
+/// use std::sync::Arc;
+/// let grid = Arc::new(SyncSqGrid::wilsons(8, &mut rand::thread_rng()));
+/// let shared = Arc::clone(&grid);
+/// let len = std::thread::spawn(move || shared.shortest_path(0.into(), 63.into()).len());
+/// assert_eq!(len.join().unwrap(), grid.shortest_path(0.into(), 63.into()).len());
+/// ```
+pub type SyncSqGrid = SqGridOf<RwLock<Vec<Index>>>;
+
+/// A square grid, generic over how the links of its cells are stored (see [`LinkStore`]).  Use
+/// the [`SqGrid`] and [`SyncSqGrid`] aliases rather than naming this type directly.
+///
+/// Deserialized grids are rebuilt from their size and topology, and rejected with a
+/// [`FormatError`] if a cell does not match its position, a link is not between neighbors or is
+/// missing from the other cell, or an opening is not on the boundary.
 // todo: impl Default, Index, Display
-#[derive(Clone, Debug)]
-pub struct SqGrid {
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "SqGridData", bound(deserialize = "L: LinkStore"))
+)]
+pub struct SqGridOf<L> {
+    size: RowSize,
+    cells: Vec<SqCell<L>>,
+    topology: Topology,
+    openings: Vec<(Index, Cardinal)>,
+}
+/// A grid as it is deserialized, before it is checked and turned into a [`SqGridOf`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SqGridData {
     size: RowSize,
-    cells: Vec<SqCell>,
+    cells: Vec<SqCellData>,
+    topology: Topology,
+    openings: Vec<(Index, Cardinal)>,
+}
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SqCellData {
+    id: Index,
+    links: Vec<Index>,
+    neighbors: Vec<Index>,
+}
+#[cfg(feature = "serde")]
+impl<L: LinkStore> std::convert::TryFrom<SqGridData> for SqGridOf<L> {
+    type Error = FormatError;
+    fn try_from(data: SqGridData) -> Result<Self, FormatError> {
+        use crate::maze::Cell;
+        let size = *data.size;
+        if size.checked_mul(size) != Some(data.cells.len()) {
+            return Err(FormatError::CellCount {
+                expected: size.saturating_mul(size),
+                found: data.cells.len(),
+            });
+        }
+        if !data.topology.fits(size) {
+            return Err(FormatError::InvalidSize {
+                rows: size,
+                cols: size,
+            });
+        }
+        let mut grid = Self::with_topology(size, data.topology);
+        for (cell, expected) in data.cells.iter().zip(grid.cells.iter()) {
+            if cell.id != expected.id() || cell.neighbors[..] != *expected.neighbor_ids() {
+                return Err(FormatError::InvalidCell(expected.id()));
+            }
+            for n in &cell.links {
+                let count = |links: &[Index], id: Index| links.iter().filter(|l| **l == id).count();
+                let back = data
+                    .cells
+                    .get(**n)
+                    .map_or(0, |other| count(&other.links, cell.id));
+                if !expected.has_neighbor(*n) || count(&cell.links, *n) != back {
+                    return Err(FormatError::InvalidLink(cell.id));
+                }
+                expected.unchecked_link(*n);
+            }
+        }
+        for (id, dir) in data.openings {
+            grid.open_boundary(id, dir)
+                .map_err(|_| FormatError::InvalidOpening(id))?;
+        }
+        Ok(grid)
+    }
+}
+impl<L: LinkStore> Clone for SqGridOf<L> {
+    fn clone(&self) -> Self {
+        self.convert()
+    }
 }
-impl SqGrid {
-    fn blank(size: usize) -> Self {
+impl<L: LinkStore> SqGridOf<L> {
+    /// Copies the grid into one that stores its links in `M`, e.g. turning a [`SqGrid`] into a
+    /// [`SyncSqGrid`] that can be rendered with
+    /// [`render_rgba_parallel`](crate::render::Renderer::render_rgba_parallel).
+    ///
+    /// # Example
+    /// ```
+    /// use mazes::maze::sq::{SqGrid, SyncSqGrid};
+    /// use mazes::maze::Grid;
+    /// let grid = SqGrid::wilsons(8, &mut rand::thread_rng());
+    /// let sync: SyncSqGrid = grid.convert();
+    /// let path = grid.shortest_path(0.into(), 63.into());
+    /// assert_eq!(sync.shortest_path(0.into(), 63.into()).len(), path.len());
+    /// ```
+    pub fn convert<M: LinkStore>(&self) -> SqGridOf<M> {
+        SqGridOf {
+            size: self.size,
+            cells: self.cells.iter().map(SqCell::convert).collect(),
+            topology: self.topology,
+            openings: self.openings.clone(),
+        }
+    }
+    fn blank(size: usize, topology: Topology) -> Self {
         Self {
             size: size.into(),
             cells: Vec::with_capacity(size * size),
+            topology,
+            openings: Vec::new(),
         }
     }
     pub fn new(size: usize) -> Self {
-        let mut grid = SqGrid::blank(size);
+        Self::with_topology(size, Topology::Plane)
+    }
+    /// Creates a grid whose edges wrap around according to `topology`.  Use one of the `carve_`
+    /// methods on [`Grid`] (e.g. [`Grid::carve_wilsons`]) to turn it into a maze.
+    ///
+    /// Panics if `topology` wraps around and `size` is below 3 (see [`Topology::fits`]).
+    ///
+    /// # Example
+    /// ```
+    /// use mazes::maze::{Grid, Topology};
+    /// use mazes::SqGrid;
+    /// let grid = SqGrid::with_topology(6, Topology::Cylinder);
+    /// grid.carve_wilsons(&mut rand::thread_rng());
+    /// ```
+    pub fn with_topology(size: usize, topology: Topology) -> Self {
+        assert!(
+            topology.fits(size),
+            "a wrapping grid needs at least 3 cells per side"
+        );
+        let mut grid = Self::blank(size, topology);
         let id = |row: usize, col: usize| Index::from(row * size + col);
         for r in 0..size {
             for c in 0..size {
@@ -42,11 +182,47 @@ impl SqGrid {
         }
         grid
     }
+    /// Removes the outer wall of the cell in the given direction, e.g. to mark an entrance.
+    pub fn open_boundary(&mut self, id: Index, dir: Cardinal) -> Result<(), NoBoundaryError> {
+        if !self.has_boundary(id, dir) {
+            return Err(NoBoundaryError::new(id, dir));
+        }
+        if !self.is_open(id, dir) {
+            self.openings.push((id, dir));
+        }
+        Ok(())
+    }
+    /// Closes all openings made with [`SqGrid::open_boundary`].
+    pub fn close_boundaries(&mut self) {
+        self.openings.clear();
+    }
+    /// Replaces any existing openings with an entrance and an exit on the boundary, placed as far
+    /// apart as possible (see [`CardinalGrid::farthest_boundary_cells`]).  Returns the entrance
+    /// and exit cells.
+    ///
+    /// # Example
+    /// ```
+    /// use mazes::maze::{CardinalGrid, Grid};
+    /// use mazes::SqGrid;
+    /// let mut grid = SqGrid::wilsons(8, &mut rand::thread_rng());
+    /// let (entrance, exit) = grid.place_entrances().unwrap();
+    /// let solution = grid.shortest_path(entrance, exit);
+    /// assert_eq!(grid.openings().len(), 2);
+    /// ```
+    pub fn place_entrances(&mut self) -> Option<(Index, Index)> {
+        let (entrance, exit) = self.farthest_boundary_cells()?;
+        self.close_boundaries();
+        for id in [entrance, exit] {
+            let dir = self.find_boundary(id)?;
+            self.open_boundary(id, dir).ok()?;
+        }
+        Some((entrance, exit))
+    }
 }
-impl Grid for SqGrid {}
+impl<L: LinkStore> Grid for SqGridOf<L> {}
 
-impl GridProps for SqGrid {
-    type C = SqCell;
+impl<L: LinkStore> GridProps for SqGridOf<L> {
+    type C = SqCell<L>;
     fn setup(size: usize) -> Self {
         Self::new(size)
     }
@@ -57,18 +233,18 @@ impl GridProps for SqGrid {
         &self.cells
     }
 }
-impl std::ops::Index<usize> for SqGrid {
-    type Output = SqCell;
+impl<L: LinkStore> std::ops::Index<usize> for SqGridOf<L> {
+    type Output = SqCell<L>;
     fn index(&self, index: usize) -> &Self::Output {
         &self.cells[index]
     }
 }
-impl std::ops::IndexMut<usize> for SqGrid {
+impl<L: LinkStore> std::ops::IndexMut<usize> for SqGridOf<L> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         &mut self.cells[index]
     }
 }
-impl CardinalGrid for SqGrid {
+impl<L: LinkStore> CardinalGrid for SqGridOf<L> {
     fn row_size(&self) -> RowSize {
         self.size
     }
@@ -78,8 +254,14 @@ impl CardinalGrid for SqGrid {
     fn dimensions(&self) -> (RowSize, ColSize) {
         (self.row_size(), self.row_size())
     }
+    fn topology(&self) -> Topology {
+        self.topology
+    }
+    fn openings(&self) -> &[(Index, Cardinal)] {
+        &self.openings
+    }
 }
-impl CoordLookup for SqGrid {
+impl<L: LinkStore> CoordLookup for SqGridOf<L> {
     fn get_id(&self, coord: &Coord) -> Index {
         coord.y() * self.size.into() + coord.x()
     }
@@ -107,79 +289,22 @@ impl CoordLookup for SqGrid {
     }
 }
 
-impl SqGrid {
+impl<L: LinkStore> SqGridOf<L> {
     pub fn render_options<'f, 'o, 'g>(
         &'g self,
         opts: &'o BasicOpts<'f>,
-    ) -> RenderGrid<'f, 'o, 'g, SqGrid> {
+    ) -> RenderGrid<'f, 'o, 'g, Self> {
         RenderGrid::with_options(self, opts)
     }
-    pub fn render_defaults<'f, 'o, 'g>(&'g self) -> RenderGrid<'f, 'o, 'g, SqGrid> {
+    pub fn render_defaults<'f, 'o, 'g>(&'g self) -> RenderGrid<'f, 'o, 'g, Self> {
         RenderGrid::new(self)
     }
-    fn draw_joint_corner(
-        &self,
-        block: &FloatBlock,
-        d: &Ordinal,
-        pad: u32,
-        color: &Rgba<u8>,
-        opts: &BasicOpts,
-        image: &mut RgbaImage,
-    ) {
-        let corner = block.corner(d);
-        let x = d.side_x();
-        let y = d.side_y();
-        let add_x = |x: f32, offset: f32, ax: &Horizontal| -> f32 {
-            match ax {
-                Horizontal::W => x - offset,
-                Horizontal::E => x + offset,
-            }
-        };
-        let add_y = |y: f32, offset: f32, ay: &Vertical| -> f32 {
-            match ay {
-                Vertical::N => y - offset,
-                Vertical::S => y + offset,
-            }
-        };
-        let floor = opts.border_width() / 2;
-        let rem = opts.border_width() % 2;
-        for i in 0..(floor + rem) {
-            let pad = if opts.tri_joints() { pad + i } else { pad };
-            let cx = add_x(corner.0, i as f32, &x);
-            let cy = add_y(corner.1, i as f32, &y);
-            //Horizontal joint line
-            let line1 = ((cx, cy), (block.x_offset(pad, &x), cy));
-            // Vertical joint line
-            let line2 = ((cx, cy), (cx, block.y_offset(pad, &y)));
-            draw_line_segment_mut(image, line1.0, line1.1, *color);
-            draw_line_segment_mut(image, line2.0, line2.1, *color);
-        }
-    }
 }
 
-impl Renderable for SqGrid {
+impl<L: LinkStore> Renderable for SqGridOf<L> {
     type B = UnsignedIntBlock;
     fn draw_joint(&self, id: Index, block: &Self::B, image: &mut RgbaImage, opts: &BasicOpts) {
-        let pad = opts.joint_size();
-        if pad == 0 {
-            return;
-        }
-        let mut block = FloatBlock::from(block);
-        block.x1 -= 1.0;
-        block.y1 -= 1.0;
-        block.x2 -= (opts.border_width()) as f32 - 1.0;
-        block.y2 -= (opts.border_width()) as f32 - 1.0;
-        for d in Ordinal::iter() {
-            let x: Cardinal = d.side_x().into();
-            let y: Cardinal = d.side_y().into();
-            let jc = opts.joint_color();
-            match (self.has_boundary(id, x), self.has_boundary(id, y)) {
-                (false, false) => self.draw_joint_corner(&block, &d, pad, jc, opts, image),
-                (true, false) => self.draw_joint_corner(&block, &d, pad, jc, opts, image),
-                (false, true) => self.draw_joint_corner(&block, &d, pad, jc, opts, image),
-                _ => {}
-            };
-        }
+        draw_sq_joint(self, id, block, image, opts)
     }
     fn draw_block_outline(
         &self,
@@ -188,42 +313,7 @@ impl Renderable for SqGrid {
         image: &mut RgbaImage,
         opts: &BasicOpts,
     ) {
-        if opts.border_width() == 0 {
-            return;
-        }
-        let cell = self.lookup(id);
-        let border = opts.border_width();
-        for d in Cardinal::iter() {
-            if d.north() || d.west() {
-                // adjust northwest corner (otherwise there is an empty square in northwest corner)
-                if self.has_boundary(id, Cardinal::N) && self.has_boundary(id, Cardinal::W) {
-                    let mut block = block.clone();
-                    block.y1 -= border;
-                    block.x1 -= border;
-                    for i in 0..(border as i32) {
-                        block.draw_edge(&d, i, image, opts)
-                    }
-                }
-                if self.has_boundary(id, d) {
-                    for i in 0 - (border as i32)..0 {
-                        block.draw_edge(&d, i, image, opts)
-                    }
-                }
-                continue;
-            }
-            if let Some(n) = self.neighbor(id, &d) {
-                if !cell.links().borrow().contains(&n) {
-                    for i in 0..border {
-                        block.draw_edge(&d, i as i32, image, opts);
-                    }
-                }
-            } else if self.has_boundary(id, d) {
-                for i in 0..border {
-                    block.draw_edge(&d, i as i32, image, opts);
-                }
-            } else {
-            }
-        }
+        draw_sq_outline(self, id, block, image, opts)
     }
     fn fill_block_bg(
         &self,
@@ -233,15 +323,7 @@ impl Renderable for SqGrid {
         image: &mut RgbaImage,
         opts: &BasicOpts,
     ) {
-        let x: u32 = block.x1;
-        let y: u32 = block.y1;
-        let border = opts.border_width();
-        let s = if opts.fill_empty_passages() {
-            opts.block_size() + border
-        } else {
-            opts.block_size()
-        };
-        draw_filled_rect_mut(image, Rect::at(x as i32, y as i32).of_size(s, s), *color)
+        fill_sq_block_bg(block, color, image, opts)
     }
     fn draw_block_text(
         &self,
@@ -252,30 +334,212 @@ impl Renderable for SqGrid {
         image: &mut RgbaImage,
         opts: &BasicOpts,
     ) {
-        if !opts.text_labels() {
-            return;
+        draw_sq_block_text(block, text, color, image, opts)
+    }
+    fn image_dimensions(&self, opts: &BasicOpts) -> (u32, u32) {
+        sq_image_dimensions(self, opts)
+    }
+    fn band_cells(&self, top: u32, bottom: u32, opts: &BasicOpts) -> Vec<Range<usize>> {
+        vec![sq_band_cells(self, top, bottom, opts)]
+    }
+}
+
+// The functions below render square blocks for any [`CardinalGrid`] and are shared by the grid
+// types that are made up of square cells.
+
+pub(in crate) fn draw_sq_joint<G: CardinalGrid>(
+    grid: &G,
+    id: Index,
+    block: &UnsignedIntBlock,
+    image: &mut RgbaImage,
+    opts: &BasicOpts,
+) {
+    let pad = opts.joint_size();
+    if pad == 0 {
+        return;
+    }
+    let mut block = FloatBlock::from(block);
+    block.x1 -= 1.0;
+    block.y1 -= 1.0;
+    block.x2 -= (opts.border_width()) as f32 - 1.0;
+    block.y2 -= (opts.border_width()) as f32 - 1.0;
+    for d in Ordinal::iter() {
+        let x: Cardinal = d.side_x().into();
+        let y: Cardinal = d.side_y().into();
+        let jc = opts.joint_color();
+        match (grid.has_boundary(id, x), grid.has_boundary(id, y)) {
+            (false, false) => draw_joint_corner(&block, &d, pad, jc, opts, image),
+            (true, false) => draw_joint_corner(&block, &d, pad, jc, opts, image),
+            (false, true) => draw_joint_corner(&block, &d, pad, jc, opts, image),
+            _ => {}
+        };
+    }
+}
+pub(in crate) fn draw_sq_outline<G: CardinalGrid>(
+    grid: &G,
+    id: Index,
+    block: &UnsignedIntBlock,
+    image: &mut RgbaImage,
+    opts: &BasicOpts,
+) {
+    if opts.border_width() == 0 {
+        return;
+    }
+    let border = opts.border_width();
+    // passages that wrap around to the opposite edge are drawn in `wrap_color`
+    let wrap_color = |d: &Cardinal| match grid.has_dir_link(id, d) {
+        true => opts.wrap_color(),
+        false => opts.border_color(),
+    };
+    for d in Cardinal::iter() {
+        if d.north() || d.west() {
+            // adjust northwest corner (otherwise there is an empty square in northwest corner),
+            // leaving out sides that have been opened
+            if grid.is_edge(id, Cardinal::N)
+                && grid.is_edge(id, Cardinal::W)
+                && !grid.is_open(id, d)
+            {
+                let mut block = block.clone();
+                block.y1 -= border;
+                block.x1 -= border;
+                for i in 0..(border as i32) {
+                    block.draw_edge(&d, i, image, opts)
+                }
+            }
+            if grid.has_boundary(id, d) && sq_has_wall(grid, id, d) {
+                for i in 0 - (border as i32)..0 {
+                    block.draw_edge(&d, i, image, opts)
+                }
+            } else if grid.wraps(id, d) {
+                for i in 0 - (border as i32)..0 {
+                    block.draw_edge_color(&d, i, wrap_color(&d), image)
+                }
+            }
+            continue;
         }
-        let scale = opts.font_scale();
-        let x: u32;
-        let y: u32;
-        if opts.center_labels() {
-            let center = block.text_center(opts);
-            x = center.0;
-            y = center.1;
-        } else {
-            x = block.x1 + opts.block_padding();
-            y = block.y1 + opts.block_padding();
+        if grid.wraps(id, d) {
+            for i in 0..border {
+                block.draw_edge_color(&d, i as i32, wrap_color(&d), image);
+            }
+        } else if sq_has_wall(grid, id, d) {
+            for i in 0..border {
+                block.draw_edge(&d, i as i32, image, opts);
+            }
         }
-        draw_text_mut(image, *color, x, y, scale, opts.font(), text);
     }
-    fn image_dimensions(&self, opts: &BasicOpts) -> (u32, u32) {
-        let (rows, cols) = self.dimensions();
-        let frame = opts.frame_size();
-        let border = opts.border_width();
-        let block = opts.block_size();
-        let x = frame + border + cols.mul(block + border) as u32 + frame;
-        let y = frame + border + rows.mul(block + border) as u32 + frame;
-        (x, y)
+}
+/// Whether there is a wall on side `d` of a square cell: a boundary that has not been opened, or
+/// a side without a passage to the neighbor.  Unlinked sides that wrap around count as walls.
+pub(in crate) fn sq_has_wall<G: CardinalGrid>(grid: &G, id: Index, d: Cardinal) -> bool {
+    match grid.has_boundary(id, d) {
+        true => !grid.is_open(id, d),
+        false => !grid.has_dir_link(id, &d),
+    }
+}
+pub(in crate) fn fill_sq_block_bg(
+    block: &UnsignedIntBlock,
+    color: &Rgba<u8>,
+    image: &mut RgbaImage,
+    opts: &BasicOpts,
+) {
+    let x: u32 = block.x1;
+    let y: u32 = block.y1;
+    let border = opts.border_width();
+    let s = if opts.fill_empty_passages() {
+        opts.block_size() + border
+    } else {
+        opts.block_size()
+    };
+    draw_filled_rect_mut(image, Rect::at(x as i32, y as i32).of_size(s, s), *color)
+}
+pub(in crate) fn draw_sq_block_text(
+    block: &UnsignedIntBlock,
+    text: &str,
+    color: &Rgba<u8>,
+    image: &mut RgbaImage,
+    opts: &BasicOpts,
+) {
+    if !opts.text_labels() {
+        return;
+    }
+    let scale = opts.font_scale();
+    let x: u32;
+    let y: u32;
+    if opts.center_labels() {
+        let center = block.text_center(opts);
+        x = center.0;
+        y = center.1;
+    } else {
+        x = block.x1 + opts.block_padding();
+        y = block.y1 + opts.block_padding();
+    }
+    draw_text_mut(image, *color, x, y, scale, opts.font(), text);
+}
+pub(in crate) fn sq_image_dimensions<G: CardinalGrid>(grid: &G, opts: &BasicOpts) -> (u32, u32) {
+    let (rows, cols) = grid.dimensions();
+    let frame = opts.frame_size();
+    let border = opts.border_width();
+    let block = opts.block_size();
+    let x = frame + border + cols.mul(block + border) as u32 + frame;
+    let y = frame + border + rows.mul(block + border) as u32 + frame;
+    (x, y)
+}
+/// The rows of square blocks reaching pixel rows `top..bottom`: from the row above `top` down to
+/// the row holding `bottom`.  The range is not clamped to the number of rows in the grid.
+pub(in crate) fn sq_band_rows(top: u32, bottom: u32, opts: &BasicOpts) -> Range<usize> {
+    let offset = opts.frame_size() + opts.border_width();
+    let pitch = opts.block_size() + opts.border_width();
+    let first = (top.saturating_sub(offset) / pitch).saturating_sub(1) as usize;
+    let last = (bottom.saturating_sub(offset) / pitch + 1) as usize;
+    first..last
+}
+/// Cells are stored row by row, so the blocks reaching pixel rows `top..bottom` are a single run
+/// of whole rows.
+pub(in crate) fn sq_band_cells<G: CardinalGrid>(
+    grid: &G,
+    top: u32,
+    bottom: u32,
+    opts: &BasicOpts,
+) -> Range<usize> {
+    let (rows, cols) = grid.dimensions();
+    let band = sq_band_rows(top, bottom, opts);
+    band.start.min(*rows) * *cols..band.end.min(*rows) * *cols
+}
+fn draw_joint_corner(
+    block: &FloatBlock,
+    d: &Ordinal,
+    pad: u32,
+    color: &Rgba<u8>,
+    opts: &BasicOpts,
+    image: &mut RgbaImage,
+) {
+    let corner = block.corner(d);
+    let x = d.side_x();
+    let y = d.side_y();
+    let add_x = |x: f32, offset: f32, ax: &Horizontal| -> f32 {
+        match ax {
+            Horizontal::W => x - offset,
+            Horizontal::E => x + offset,
+        }
+    };
+    let add_y = |y: f32, offset: f32, ay: &Vertical| -> f32 {
+        match ay {
+            Vertical::N => y - offset,
+            Vertical::S => y + offset,
+        }
+    };
+    let floor = opts.border_width() / 2;
+    let rem = opts.border_width() % 2;
+    for i in 0..(floor + rem) {
+        let pad = if opts.tri_joints() { pad + i } else { pad };
+        let cx = add_x(corner.0, i as f32, &x);
+        let cy = add_y(corner.1, i as f32, &y);
+        //Horizontal joint line
+        let line1 = ((cx, cy), (block.x_offset(pad, &x), cy));
+        // Vertical joint line
+        let line2 = ((cx, cy), (cx, block.y_offset(pad, &y)));
+        draw_line_segment_mut(image, line1.0, line1.1, *color);
+        draw_line_segment_mut(image, line2.0, line2.1, *color);
     }
 }
 
@@ -283,8 +547,11 @@ impl Renderable for SqGrid {
 pub(in crate) mod tests {
     use super::*;
     use crate::maze::sq::SqGrid;
+    use crate::maze::Cell;
     use crate::maze::Grid;
     use crate::render::Renderer;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
     use std::path::Path;
     pub(in crate) fn new_maze(size: usize) -> SqGrid {
         let grid = SqGrid::new(size);
@@ -335,4 +602,158 @@ pub(in crate) mod tests {
         }
         Ok(())
     }
+    #[test]
+    fn wrapping_neighbors() {
+        let cylinder = SqGrid::with_topology(4, Topology::Cylinder);
+        assert_eq!(cylinder.neighbor(0.into(), &Cardinal::W), Some(3.into()));
+        assert_eq!(cylinder.neighbor(7.into(), &Cardinal::E), Some(4.into()));
+        assert_eq!(cylinder.neighbor(1.into(), &Cardinal::N), None);
+        assert!(cylinder.has_boundary(0.into(), Cardinal::N));
+        assert!(!cylinder.has_boundary(0.into(), Cardinal::W));
+        let torus = SqGrid::with_topology(4, Topology::Torus);
+        assert_eq!(torus.neighbor(1.into(), &Cardinal::N), Some(13.into()));
+        assert_eq!(torus.neighbor(14.into(), &Cardinal::S), Some(2.into()));
+        assert!(torus.wraps(15.into(), Cardinal::S));
+        assert!(torus.iter().all(|c| c.neighbor_ids().len() == 4));
+        assert_eq!(torus.find_boundary(0.into()), None);
+    }
+    #[test]
+    #[should_panic(expected = "at least 3 cells")]
+    fn narrow_cylinder() {
+        assert!(Topology::Cylinder.fits(3));
+        assert!(!Topology::Cylinder.fits(2));
+        assert!(Topology::Plane.fits(2));
+        SqGrid::with_topology(2, Topology::Cylinder);
+    }
+    #[test]
+    fn render_torus() -> Result<(), image::ImageError> {
+        let mut rng = SplitMix64::seed_from_u64(271);
+        let grid = SqGrid::with_topology(6, Topology::Torus);
+        grid.carve_wilsons(&mut rng);
+        let dist = grid.distances(Index::zero());
+        assert_eq!(dist.map_ref().len(), *grid.capacity());
+        grid.render_defaults().save_render(Path::new("torus.png"))
+    }
+    #[test]
+    fn render_entrances() -> Result<(), image::ImageError> {
+        let mut rng = SplitMix64::seed_from_u64(39);
+        let mut grid = SqGrid::wilsons(8, &mut rng);
+        let (entrance, exit) = grid.place_entrances().unwrap();
+        assert!(grid.find_boundary(entrance).is_some());
+        assert!(grid.find_boundary(exit).is_some());
+        assert_eq!(grid.openings().len(), 2);
+        assert!(grid.open_boundary(9.into(), Cardinal::N).is_err());
+        let path = grid.shortest_path(entrance, exit);
+        let (_, longest) = grid.distances(entrance).max_dist();
+        assert!(path.len() > 1 && path.len() - 1 <= longest);
+        path.render_defaults()
+            .save_render(Path::new("entrances.png"))
+    }
+    #[test]
+    fn open_northwest_corner() {
+        let opts = BasicOpts::default();
+        for d in [Cardinal::N, Cardinal::W] {
+            let mut grid = SqGrid::wilsons(4, &mut SplitMix64::seed_from_u64(39));
+            grid.open_boundary(Index::zero(), d).unwrap();
+            let image = grid.render_defaults().render_rgba();
+            let block = UnsignedIntBlock::new(&grid, Index::zero(), &opts);
+            let (cx, cy) = ((block.x1 + block.x2) / 2, (block.y1 + block.y2) / 2);
+            let (x, y) = match d {
+                Cardinal::N => (cx, block.y1 - 1),
+                _ => (block.x1 - 1, cy),
+            };
+            assert_eq!(image.get_pixel(x, y), opts.bg_color(), "{:?}", d);
+        }
+    }
+    #[test]
+    fn sync_grid_threads() {
+        use std::sync::Arc;
+        // the same seed carves the same maze regardless of how links are stored
+        let grid = SqGrid::wilsons(10, &mut SplitMix64::seed_from_u64(47));
+        let shared = Arc::new(SyncSqGrid::wilsons(10, &mut SplitMix64::seed_from_u64(47)));
+        let end: Index = 99.into();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                std::thread::spawn(move || match i {
+                    0 => shared.render_defaults().render_rgba().into_raw().len(),
+                    _ => shared.shortest_path(Index::zero(), end).len(),
+                })
+            })
+            .collect();
+        let lens: Vec<usize> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(
+            lens[0],
+            grid.render_defaults().render_rgba().into_raw().len()
+        );
+        assert!(lens[1..]
+            .iter()
+            .all(|l| *l == grid.shortest_path(Index::zero(), end).len()));
+        for (a, b) in grid.iter().zip(shared.iter()) {
+            assert_eq!(*a.links().borrow(), *b.links().borrow());
+        }
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() -> Result<(), serde_json::Error> {
+        use crate::util::dist::OwnedDistances;
+        use crate::util::path::OwnedPath;
+        let mut rng = SplitMix64::seed_from_u64(41);
+        let mut grid = SqGrid::with_topology(5, Topology::Cylinder);
+        grid.carve_wilsons(&mut rng);
+        grid.open_boundary(0.into(), Cardinal::N).unwrap();
+        let json = serde_json::to_string(&grid)?;
+        let copy: SqGrid = serde_json::from_str(&json)?;
+        assert_eq!(copy.topology(), Topology::Cylinder);
+        assert_eq!(copy.openings(), grid.openings());
+        for (a, b) in grid.iter().zip(copy.iter()) {
+            assert_eq!(*a.links().borrow(), *b.links().borrow());
+            assert_eq!(a.neighbor_ids(), b.neighbor_ids());
+        }
+        let dist = grid.distances(Index::zero());
+        let json = serde_json::to_string(&OwnedDistances::from(&dist))?;
+        let owned: OwnedDistances = serde_json::from_str(&json)?;
+        assert_eq!(owned.with_grid(&copy).map_ref(), dist.map_ref());
+        let path = grid.longest_path(Index::zero());
+        let json = serde_json::to_string(&OwnedPath::from(&path))?;
+        let owned: OwnedPath = serde_json::from_str(&json)?;
+        assert_eq!(owned.with_grid(&copy).get_ref(), path.get_ref());
+        let mut opts = BasicOpts::default();
+        opts.set_block_size(20);
+        let json = serde_json::to_string(&opts)?;
+        let copy: BasicOpts = serde_json::from_str(&json)?;
+        assert_eq!(copy.block_size(), 20);
+        assert_eq!(copy.wrap_color(), opts.wrap_color());
+        let partial: BasicOpts = serde_json::from_str(r#"{"frame_size": 3}"#)?;
+        assert_eq!(partial.frame_size(), 3);
+        Ok(())
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_invalid_grids() -> Result<(), serde_json::Error> {
+        let grid = SqGrid::new(2);
+        grid.link(0.into(), 1.into()).unwrap();
+        let valid = serde_json::to_value(&grid)?;
+        assert!(serde_json::from_value::<SqGrid>(valid.clone()).is_ok());
+        let invalid = |edit: &dyn Fn(&mut serde_json::Value)| {
+            let mut json = valid.clone();
+            edit(&mut json);
+            serde_json::from_value::<SqGrid>(json).is_err()
+        };
+        // diagonal link, one-sided link, wrong neighbors, missing cell, a closed side opened and
+        // a cylinder too narrow to wrap
+        assert!(invalid(&|j| {
+            j["cells"][0]["links"] = serde_json::json!([3]);
+            j["cells"][3]["links"] = serde_json::json!([0]);
+        }));
+        assert!(invalid(&|j| j["cells"][2]["links"] = serde_json::json!([0])));
+        assert!(invalid(
+            &|j| j["cells"][0]["neighbors"] = serde_json::json!([1, 2, 3])
+        ));
+        assert!(invalid(&|j| j["cells"].as_array_mut().unwrap().truncate(3)));
+        assert!(invalid(&|j| j["openings"] = serde_json::json!([[0, "S"]])));
+        assert!(invalid(&|j| j["cells"][1]["id"] = serde_json::json!(2)));
+        assert!(invalid(&|j| j["topology"] = serde_json::json!("Cylinder")));
+        Ok(())
+    }
 }
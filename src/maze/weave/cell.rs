@@ -0,0 +1,74 @@
+use crate::maze::Cell;
+use crate::util::*;
+use parse_display::Display;
+use std::cell::RefCell;
+#[derive(Clone, Debug, Display)]
+#[display("Cell {id}")]
+pub struct WeaveCell {
+    id: Index,
+    links: RefCell<Vec<Index>>,
+    neighbors: Vec<Index>,
+    /// The under cell tunneling beneath this cell, if this cell is a crossing.
+    under: Option<Index>,
+    /// The cell above this one, if this is an under cell.
+    over: Option<Index>,
+}
+impl WeaveCell {
+    pub(in crate::maze::weave) fn new(id: Index, neighbors: Vec<Index>) -> Self {
+        Self {
+            id,
+            links: RefCell::from(Vec::new()),
+            neighbors,
+            under: None,
+            over: None,
+        }
+    }
+    pub(in crate::maze::weave) fn new_under(id: Index, over: Index, neighbors: Vec<Index>) -> Self {
+        Self {
+            over: Some(over),
+            ..Self::new(id, neighbors)
+        }
+    }
+    pub(in crate::maze::weave) fn set_under(&mut self, under: Index) {
+        self.under = Some(under);
+    }
+    pub(in crate::maze::weave) fn neighbors_mut(&mut self) -> &mut Vec<Index> {
+        &mut self.neighbors
+    }
+    /// Returns the under cell beneath this cell, if a tunnel passes beneath it.
+    pub fn under(&self) -> Option<Index> {
+        self.under
+    }
+    /// Returns the cell this cell tunnels beneath, if this is an under cell.
+    pub fn over(&self) -> Option<Index> {
+        self.over
+    }
+    pub fn is_under(&self) -> bool {
+        self.over.is_some()
+    }
+    pub fn is_crossing(&self) -> bool {
+        self.under.is_some()
+    }
+}
+impl Cell for WeaveCell {
+    type Neighbors<'a> = &'a [Index];
+    fn id(&self) -> Index {
+        self.id
+    }
+    fn neighbor_ids(&self) -> &[Index] {
+        &self.neighbors
+    }
+    fn unchecked_link(&self, with: Index) {
+        self.links.borrow_mut().push(with);
+    }
+    type Links = RefCell<Vec<Index>>;
+    fn links(&self) -> &Self::Links {
+        &self.links
+    }
+    fn unchecked_unlink(&self, with: Index) {
+        let pos = self.links.borrow().iter().position(|n| *n == with);
+        if let Some(pos) = pos {
+            self.links.borrow_mut().remove(pos);
+        }
+    }
+}
@@ -0,0 +1,471 @@
+pub mod cell;
+use crate::algo::kruskals::{link_sets, DisjointSets};
+use crate::error::*;
+use crate::maze::sq::{
+    draw_sq_block_text, draw_sq_joint, draw_sq_outline, fill_sq_block_bg, sq_band_cells,
+    sq_image_dimensions,
+};
+use crate::maze::{CardinalGrid, Cell, CoordLookup, Grid, GridProps};
+use crate::render::blocks::UnsignedIntBlock;
+use crate::render::renderers::RenderGrid;
+use crate::render::{BasicOpts, Renderable};
+use crate::util::*;
+pub use cell::WeaveCell;
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_line_segment_mut};
+use imageproc::rect::Rect;
+use rand::Rng;
+use std::ops::Range;
+
+/// Direction of the passage running over a crossing; the tunnel beneath it runs perpendicular.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Crossing {
+    /// The over passage runs east-west and the tunnel runs north-south.
+    Horizontal,
+    /// The over passage runs north-south and the tunnel runs east-west.
+    Vertical,
+}
+
+/// A square grid where passages can tunnel beneath perpendicular corridors.
+///
+/// The first `size * size` cells are laid out like a [`SqGrid`](crate::SqGrid).  Each crossing
+/// appends an under cell to the end of the grid which sits beneath the crossing cell; its
+/// neighbors are the cells on either side of the tunnel.  Under cells are regular cells as far
+/// as [`Grid`] is concerned, so [`Grid::distances`] and [`Grid::shortest_path`] route through
+/// tunnels without any special handling.
+///
+/// Crossings are only created by [`WeaveGrid::kruskals_weave`] (or by hand with
+/// [`WeaveGrid::add_crossing`]).  The other generators, e.g. [`Grid::wilsons`], can be used on a
+/// `WeaveGrid` but only link neighbors on the surface, so their mazes never have any crossings.
+#[derive(Clone, Debug)]
+pub struct WeaveGrid {
+    size: RowSize,
+    cells: Vec<WeaveCell>,
+}
+impl WeaveGrid {
+    fn blank(size: usize) -> Self {
+        Self {
+            size: size.into(),
+            cells: Vec::with_capacity(size * size),
+        }
+    }
+    pub fn new(size: usize) -> Self {
+        let mut grid = WeaveGrid::blank(size);
+        let id = |row: usize, col: usize| Index::from(row * size + col);
+        for r in 0..size {
+            for c in 0..size {
+                let mut neighbors = Vec::<Index>::new();
+                let cur_id = id(r, c);
+                for d in CardinalIter::iter() {
+                    if let Some(neighbor) = grid.calc_dir(cur_id, &d) {
+                        neighbors.push(neighbor);
+                    }
+                }
+                let cell = WeaveCell::new(cur_id, neighbors);
+                grid.cells.push(cell);
+            }
+        }
+        grid
+    }
+    /// Returns the cell an under cell sits beneath, or `id` itself for cells on the surface.
+    pub fn host(&self, id: Index) -> Index {
+        self.cells
+            .get(*id)
+            .and_then(|cell| cell.over())
+            .unwrap_or(id)
+    }
+    /// Whether a tunnel can be added beneath `id`.  The cell must be an unlinked surface cell
+    /// with a surface neighbor on all four sides.
+    pub fn can_cross(&self, id: Index) -> bool {
+        match self.get(id) {
+            Some(cell) if !cell.is_under() && !cell.is_crossing() && cell.not_linked() => {
+                Cardinal::iter()
+                    .all(|d| matches!(self.calc_dir(id, &d), Some(n) if !self.lookup(n).is_under()))
+            }
+            _ => false,
+        }
+    }
+    /// Links `id` to its neighbors along the `over` passage and adds an under cell linking the
+    /// neighbors on the other two sides.  Returns the id of the new under cell, or `None` if
+    /// [`can_cross`](WeaveGrid::can_cross) is `false` for `id`.
+    pub fn add_crossing(&mut self, id: Index, over: Crossing) -> Option<Index> {
+        if !self.can_cross(id) {
+            return None;
+        }
+        let (over_dirs, under_dirs) = match over {
+            Crossing::Horizontal => ([Cardinal::E, Cardinal::W], [Cardinal::N, Cardinal::S]),
+            Crossing::Vertical => ([Cardinal::N, Cardinal::S], [Cardinal::E, Cardinal::W]),
+        };
+        let over_ids: Vec<Index> = over_dirs
+            .iter()
+            .filter_map(|d| self.calc_dir(id, d))
+            .collect();
+        let under_ids: Vec<Index> = under_dirs
+            .iter()
+            .filter_map(|d| self.calc_dir(id, d))
+            .collect();
+        let under = Index::from(self.cells.len());
+        self.cells[*id]
+            .neighbors_mut()
+            .retain(|n| over_ids.contains(n));
+        self.cells[*id].set_under(under);
+        for n in &under_ids {
+            for neighbor in self.cells[**n].neighbors_mut().iter_mut() {
+                if *neighbor == id {
+                    *neighbor = under;
+                }
+            }
+        }
+        self.cells
+            .push(WeaveCell::new_under(under, id, under_ids.clone()));
+        for n in over_ids {
+            self.link(id, n).unwrap();
+        }
+        for n in under_ids {
+            self.link(under, n).unwrap();
+        }
+        Some(under)
+    }
+    /// Kruskal's algorithm with crossings.  Crossings are added to random cells first, then the
+    /// remaining cells are linked using [`Grid::kruskals`]'s approach.
+    ///
+    /// A crossing is only added where it would not connect two cells that are already connected,
+    /// so the result is still a perfect maze.
+    pub fn kruskals_weave<R: Rng + ?Sized>(size: usize, rng: &mut R) -> Self {
+        let mut grid = Self::new(size);
+        let mut sets = DisjointSets::new(*grid.capacity());
+        for _ in 0..*grid.capacity() {
+            let id: Index = rng.gen_range(0..size * size).into();
+            if !grid.can_cross(id) {
+                continue;
+            }
+            let dir = |d: Cardinal| grid.calc_dir(id, &d).unwrap();
+            let (n, e, s, w) = (
+                dir(Cardinal::N),
+                dir(Cardinal::E),
+                dir(Cardinal::S),
+                dir(Cardinal::W),
+            );
+            if sets.same(n, s)
+                || sets.same(e, w)
+                || (sets.same(n, e) && sets.same(s, w))
+                || (sets.same(n, w) && sets.same(s, e))
+            {
+                continue;
+            }
+            let over = if rng.gen() {
+                Crossing::Horizontal
+            } else {
+                Crossing::Vertical
+            };
+            let under = grid.add_crossing(id, over).unwrap();
+            sets.push();
+            for cell in &[id, under] {
+                for link in grid.lookup(*cell).links().borrow().iter() {
+                    sets.merge(*cell, *link);
+                }
+            }
+        }
+        link_sets(&grid, &mut sets, rng);
+        grid
+    }
+    pub fn render_options<'f, 'o, 'g>(
+        &'g self,
+        opts: &'o BasicOpts<'f>,
+    ) -> RenderGrid<'f, 'o, 'g, WeaveGrid> {
+        RenderGrid::with_options(self, opts)
+    }
+    pub fn render_defaults<'f, 'o, 'g>(&'g self) -> RenderGrid<'f, 'o, 'g, WeaveGrid> {
+        RenderGrid::new(self)
+    }
+    /// Draws the inset walls of a crossing.  The over passage's walls run across the whole
+    /// block while the tunnel's walls only show between the block edges and the over passage.
+    fn draw_crossing(
+        &self,
+        id: Index,
+        block: &UnsignedIntBlock,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        let size = opts.block_size() as i32;
+        let inset = opts.weave_inset().min(opts.block_size() / 2) as i32;
+        let far = size - inset;
+        let (x, y) = (block.x1 as i32, block.y1 as i32);
+        for (cx, cy) in [(x, y), (x + far, y), (x, y + far), (x + far, y + far)] {
+            let rect = Rect::at(cx, cy).of_size(inset as u32, inset as u32);
+            draw_filled_rect_mut(image, rect, *opts.bg_color());
+        }
+        let horizontal = self.neighbor(id, &Cardinal::E).is_some();
+        let color = *opts.border_color();
+        let mut line = |a: (i32, i32), b: (i32, i32)| {
+            let (a, b) = match horizontal {
+                true => (a, b),
+                false => ((a.1, a.0), (b.1, b.0)),
+            };
+            let start = ((x + a.0) as f32, (y + a.1) as f32);
+            let end = ((x + b.0) as f32, (y + b.1) as f32);
+            draw_line_segment_mut(image, start, end, color);
+        };
+        // Lines are given as if the over passage is horizontal and transposed otherwise.
+        for i in 0..opts.border_width() as i32 {
+            let near = inset - 1 - i;
+            let far = far + i;
+            // over passage
+            line((0, near), (size - 1, near));
+            line((0, far), (size - 1, far));
+            // tunnel
+            line((near, 0), (near, inset - 1));
+            line((far, 0), (far, inset - 1));
+            line((near, size - inset), (near, size - 1));
+            line((far, size - inset), (far, size - 1));
+        }
+    }
+}
+impl Grid for WeaveGrid {}
+
+impl GridProps for WeaveGrid {
+    type C = WeaveCell;
+    fn setup(size: usize) -> Self {
+        Self::new(size)
+    }
+    fn capacity(&self) -> Capacity {
+        self.cells.len().into()
+    }
+    fn cells(&self) -> &Vec<<Self as GridProps>::C> {
+        &self.cells
+    }
+}
+impl std::ops::Index<usize> for WeaveGrid {
+    type Output = WeaveCell;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.cells[index]
+    }
+}
+impl CardinalGrid for WeaveGrid {
+    fn row_size(&self) -> RowSize {
+        self.size
+    }
+    fn col_size(&self) -> ColSize {
+        self.size
+    }
+    fn dimensions(&self) -> (RowSize, ColSize) {
+        (self.row_size(), self.row_size())
+    }
+    fn is_edge_north(&self, id: Index) -> bool {
+        self.host(id).lt(self.row_size())
+    }
+    fn is_edge_east(&self, id: Index) -> bool {
+        self.host(id).rem(self.row_size()) == *self.row_size() - 1
+    }
+    fn is_edge_south(&self, id: Index) -> bool {
+        self.host(id).div(self.row_size()) == *self.row_size() - 1
+    }
+    fn is_edge_west(&self, id: Index) -> bool {
+        self.host(id).rem(self.row_size()) == 0
+    }
+    /// The tunnel beneath a crossing passes through the sides the crossing itself has no neighbor
+    /// on, so those sides are linked when the under cell is.
+    fn has_dir_link(&self, id: Index, d: &Cardinal) -> bool {
+        let cell = match self.get(id) {
+            Some(cell) => cell,
+            None => return false,
+        };
+        match self.neighbor(id, d) {
+            Some(n) => cell.has_link(n),
+            None => cell
+                .under()
+                .is_some_and(|under| self.has_dir_link(under, d)),
+        }
+    }
+    /// Neighbors are found from the position of the cell (or the cell above an under cell).  When
+    /// a tunnel passes beneath the cell in that direction its under cell is returned instead, and
+    /// nothing is returned for directions the cell's passages do not run in.
+    fn calc_dir(&self, id: Index, dir: &Cardinal) -> Option<Index> {
+        let host = self.host(id);
+        let n = match dir {
+            Cardinal::N => self.calc_north(host),
+            Cardinal::E => self.calc_east(host),
+            Cardinal::S => self.calc_south(host),
+            Cardinal::W => self.calc_west(host),
+        }?;
+        let cell = match self.get(id) {
+            Some(cell) => cell,
+            // still building the grid in `new()`
+            None => return Some(n),
+        };
+        if cell.has_neighbor(n) {
+            Some(n)
+        } else {
+            self.get(n)
+                .and_then(|neighbor| neighbor.under())
+                .filter(|under| cell.has_neighbor(*under))
+        }
+    }
+}
+impl CoordLookup for WeaveGrid {
+    fn get_id(&self, coord: &Coord) -> Index {
+        coord.y() * self.size.into() + coord.x()
+    }
+    fn try_get_id(&self, coord: &Coord) -> Result<Index, OutOfBoundsCoordError> {
+        if coord.x().lt(self.size) && coord.y().lt(self.size) {
+            Ok(self.get_id(coord))
+        } else {
+            Err(OutOfBoundsCoordError::new(*coord))
+        }
+    }
+    /// Under cells share the coordinates of the cell above them.
+    fn get_coords(&self, id: Index) -> Coord {
+        let host = self.host(id);
+        let x = host.rem(self.size);
+        let y = host.div(self.size);
+        Coord::new(x.into(), y.into())
+    }
+    fn try_get_coords(&self, id: Index) -> Result<Coord, OutOfBoundsError> {
+        if id.lt(self.capacity()) {
+            Ok(self.get_coords(id))
+        } else {
+            Err(OutOfBoundsError::new(id))
+        }
+    }
+}
+
+impl Renderable for WeaveGrid {
+    type B = UnsignedIntBlock;
+    fn render_block(
+        &self,
+        id: Index,
+        block: &Self::B,
+        text: &str,
+        bg_color: &Rgba<u8>,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        // under cells are drawn along with the crossing above them
+        if self.lookup(id).is_under() {
+            return;
+        }
+        self.fill_block_bg(id, block, bg_color, image, opts);
+        self.draw_block_outline(id, block, image, opts);
+        if opts.show_joints() {
+            self.draw_joint(id, block, image, opts);
+        }
+        self.draw_block_text(id, block, text, opts.label_color(), image, opts);
+    }
+    fn draw_joint(&self, id: Index, block: &Self::B, image: &mut RgbaImage, opts: &BasicOpts) {
+        draw_sq_joint(self, id, block, image, opts)
+    }
+    fn draw_block_outline(
+        &self,
+        id: Index,
+        block: &Self::B,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        draw_sq_outline(self, id, block, image, opts);
+        if self.lookup(id).is_crossing() {
+            self.draw_crossing(id, block, image, opts);
+        }
+    }
+    fn fill_block_bg(
+        &self,
+        _id: Index,
+        block: &Self::B,
+        color: &Rgba<u8>,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        fill_sq_block_bg(block, color, image, opts)
+    }
+    fn draw_block_text(
+        &self,
+        _id: Index,
+        block: &Self::B,
+        text: &str,
+        color: &Rgba<u8>,
+        image: &mut RgbaImage,
+        opts: &BasicOpts,
+    ) {
+        draw_sq_block_text(block, text, color, image, opts)
+    }
+    fn image_dimensions(&self, opts: &BasicOpts) -> (u32, u32) {
+        sq_image_dimensions(self, opts)
+    }
+    /// Surface cells are stored row by row, while under cells can sit beneath any crossing.
+    fn band_cells(&self, top: u32, bottom: u32, opts: &BasicOpts) -> Vec<Range<usize>> {
+        vec![
+            sq_band_cells(self, top, bottom, opts),
+            *self.size.cap()..self.cells.len(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Renderer;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn tunnel_routing() {
+        let mut grid = WeaveGrid::new(3);
+        let under = grid.add_crossing(4.into(), Crossing::Horizontal).unwrap();
+        assert_eq!(grid.host(under), 4.into());
+        assert_eq!(grid.neighbor(1.into(), &Cardinal::S), Some(under));
+        assert!(grid.add_crossing(4.into(), Crossing::Vertical).is_none());
+        let path = grid.shortest_path(1.into(), 7.into());
+        assert_eq!(path.get_ref(), &[1.into(), under, 7.into()]);
+        let path = grid.shortest_path(3.into(), 5.into());
+        assert_eq!(path.get_ref(), &[3.into(), 4.into(), 5.into()]);
+    }
+    #[test]
+    fn kruskals_weave() -> Result<(), image::ImageError> {
+        let mut rng = SplitMix64::seed_from_u64(852);
+        let grid = WeaveGrid::kruskals_weave(8, &mut rng);
+        assert!(grid.capacity().gt(64usize));
+        let dist = grid.distances(Index::zero());
+        assert_eq!(dist.map_ref().len(), *grid.capacity());
+        let links: usize = grid.iter().map(|c| c.links().borrow().len()).sum();
+        assert_eq!(links / 2, grid.capacity().minus(1usize));
+        grid.render_defaults()
+            .save_render(std::path::Path::new("weave.png"))
+    }
+    #[test]
+    fn wilsons_without_crossings() {
+        let mut rng = SplitMix64::seed_from_u64(852);
+        let grid = WeaveGrid::wilsons(8, &mut rng);
+        assert_eq!(*grid.capacity(), 64);
+        assert!(grid.iter().all(|c| !c.is_crossing() && !c.is_under()));
+        assert!(grid.validate().is_perfect());
+    }
+    #[test]
+    fn tunnel_mouths() {
+        let opts = BasicOpts::default();
+        let border = *opts.border_color();
+        for (over, tunnel) in [
+            (Crossing::Horizontal, [Cardinal::N, Cardinal::S]),
+            (Crossing::Vertical, [Cardinal::E, Cardinal::W]),
+        ] {
+            let mut grid = WeaveGrid::new(3);
+            grid.add_crossing(4.into(), over).unwrap();
+            let image = grid.render_options(&opts).render_rgba();
+            // the middle of each side of the crossing, on the line its wall would be drawn on
+            let mouth = |id: usize, d: Cardinal| {
+                let block = UnsignedIntBlock::new(&grid, id.into(), &opts);
+                let (cx, cy) = ((block.x1 + block.x2) / 2, (block.y1 + block.y2) / 2);
+                let (x, y) = match d {
+                    Cardinal::N => (cx, block.y1 - 1),
+                    Cardinal::E => (block.x2, cy),
+                    Cardinal::S => (cx, block.y2),
+                    Cardinal::W => (block.x1 - 1, cy),
+                };
+                *image.get_pixel(x, y)
+            };
+            for d in tunnel {
+                assert_ne!(mouth(4, d), border, "{:?} tunnel closed on {:?}", over, d);
+            }
+            // cell 0 is not linked at all
+            assert_eq!(mouth(0, Cardinal::S), border);
+            assert_eq!(mouth(0, Cardinal::E), border);
+        }
+    }
+}
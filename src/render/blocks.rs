@@ -8,10 +8,54 @@ use imageproc::drawing::{draw_antialiased_line_segment_mut, draw_line_segment_mu
 use imageproc::pixelops::interpolate;
 use parse_display::Display;
 
-pub trait BlockCoords {}
-impl BlockCoords for FloatBlock {}
-impl BlockCoords for UnsignedIntBlock {}
-impl BlockCoords for SignedIntBlock {}
+pub trait BlockCoords {
+    /// The first and last pixel rows of the block, clamped to the top of the image.
+    fn rows(&self) -> (u32, u32);
+    /// Moves the block up by `dy` pixels, e.g. to draw it onto an image holding only part of the
+    /// full render.
+    ///
+    /// Panics if a block with unsigned coordinates starts less than `dy` pixels from the top.
+    fn shift_up(&self, dy: u32) -> Self;
+}
+impl BlockCoords for FloatBlock {
+    fn rows(&self) -> (u32, u32) {
+        (self.y1.max(0.0) as u32, self.y2.max(0.0).ceil() as u32)
+    }
+    fn shift_up(&self, dy: u32) -> Self {
+        Self {
+            y1: self.y1 - dy as f32,
+            y2: self.y2 - dy as f32,
+            ..*self
+        }
+    }
+}
+impl BlockCoords for UnsignedIntBlock {
+    fn rows(&self) -> (u32, u32) {
+        (self.y1, self.y2)
+    }
+    fn shift_up(&self, dy: u32) -> Self {
+        Self {
+            y1: self
+                .y1
+                .checked_sub(dy)
+                .expect("block shifted above the image"),
+            y2: self.y2 - dy,
+            ..*self
+        }
+    }
+}
+impl BlockCoords for SignedIntBlock {
+    fn rows(&self) -> (u32, u32) {
+        (self.y1.max(0) as u32, self.y2.max(0) as u32)
+    }
+    fn shift_up(&self, dy: u32) -> Self {
+        Self {
+            y1: self.y1 - dy as i32,
+            y2: self.y2 - dy as i32,
+            ..*self
+        }
+    }
+}
 
 /// Stores coordinates to draw a block.
 #[derive(Clone, Debug, Display)]
@@ -147,9 +191,9 @@ impl UnsignedIntBlock {
         id: Index,
         opts: &BasicOpts,
     ) -> Self {
-        let block = opts.block_size() as u32;
-        let border = opts.border_width() as u32;
-        let frame = opts.frame_size() as u32;
+        let block = opts.block_size();
+        let border = opts.border_width();
+        let frame = opts.frame_size();
         let coords = grid.get_coords(id);
         let (col, row) = coords.unsigned_tuple();
         let x1 = col * (block + border) + border + frame;
@@ -206,9 +250,18 @@ impl UnsignedIntBlock {
         offset: i32,
         image: &mut RgbaImage,
         opts: &BasicOpts,
+    ) {
+        self.draw_edge_color(d, offset, opts.border_color(), image)
+    }
+    pub(in crate) fn draw_edge_color(
+        &self,
+        d: &Cardinal,
+        offset: i32,
+        color: &Rgba<u8>,
+        image: &mut RgbaImage,
     ) {
         let line = self.float_side(d, offset);
-        draw_line_segment_mut(image, line.0, line.1, *opts.border_color())
+        draw_line_segment_mut(image, line.0, line.1, *color)
     }
 }
 #[derive(Clone, Debug, Display)]
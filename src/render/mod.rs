@@ -1,13 +1,16 @@
 pub mod blocks;
 pub mod opts;
 pub mod renderers;
+pub mod stream;
 pub use self::opts::BasicOpts;
-use crate::maze::{Cell, Grid};
+use crate::maze::{Cell, Grid, GridProps};
 use crate::render::blocks::BlockCoords;
 use crate::util::Index;
 use image::{Rgb, Rgba, RgbaImage};
 use imageproc::drawing::draw_filled_rect_mut;
 use imageproc::rect::Rect;
+use std::io::Write;
+use std::ops::Range;
 const DEJAVU_BYTES: &[u8] = include_bytes!("../../assets/DejaVuSansMono.ttf");
 
 /// `Renderer` enables different types of images to be generated using the same grid data.
@@ -54,6 +57,42 @@ pub trait Renderer<'f>: RendererOps<'f> {
     fn save_render(&self, path: &std::path::Path) -> Result<(), image::ImageError> {
         self.render_rgba().save(path)
     }
+    /// Saves the render as a PNG, drawing and encoding it in horizontal strips so the whole image
+    /// is never held in memory (see [`stream`]).  The image is the same as the one saved by
+    /// [`save_render`](Renderer::save_render), except that
+    /// [`scale_image`](BasicOpts::scale_image) is not applied.
+    fn save_render_streamed(&self, path: &std::path::Path) -> Result<(), image::ImageError> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_png_streamed(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+    /// Writes the render to `w` as a PNG in the same way as
+    /// [`save_render_streamed`](Renderer::save_render_streamed).
+    fn write_png_streamed<W: Write>(&self, w: W) -> Result<(), image::ImageError> {
+        stream::write_png(self, w, stream::strip_height(self))
+    }
+    /// Renders the same image as [`render_rgba`](Renderer::render_rgba), drawing horizontal bands
+    /// of it on the [rayon] thread pool.  Requires the `rayon` feature.
+    ///
+    /// The renderer has to be `Sync`, and of the grids only a
+    /// [`SyncSqGrid`](crate::maze::sq::SyncSqGrid) is: every other grid keeps its links in a
+    /// [`RefCell`](std::cell::RefCell), so this method cannot be called on their renderers.  A
+    /// [`SqGrid`](crate::maze::sq::SqGrid) can be copied into a `SyncSqGrid` with
+    /// [`SqGridOf::convert`](crate::maze::sq::SqGridOf::convert) first.
+    #[cfg(feature = "rayon")]
+    fn render_rgba_parallel(&self) -> RgbaImage
+    where
+        Self: Sync,
+    {
+        let opts = self.options();
+        let output = self.render_grid_parallel();
+        if let Some(scale) = opts.scale_image() {
+            scale.scale(&output)
+        } else {
+            output
+        }
+    }
 }
 //
 pub trait RenderCell: Cell {
@@ -65,14 +104,6 @@ pub trait RenderCell: Cell {
         image: &mut RgbaImage,
     );
 }
-#[derive(Clone, Debug)]
-pub struct Block {
-    id: Index,
-    x: i32,
-    y: i32,
-    sx: u32,
-    sy: u32,
-}
 
 /// Abstraction for the rendering of an individual block using a given grid type.
 ///
@@ -122,6 +153,15 @@ pub trait Renderable: Grid {
         opts: &BasicOpts,
     );
     fn image_dimensions(&self, opts: &BasicOpts) -> (u32, u32);
+    /// Ranges of [`cells`](crate::maze::GridProps::cells) holding every block that overlaps the
+    /// pixel rows `top..bottom`, so that rendering part of the image can skip the rest.  The
+    /// ranges must be in ascending order without overlapping, so blocks are drawn in the same
+    /// order as in a full render.  They may hold other blocks as well, and default to all of the
+    /// cells.
+    fn band_cells(&self, _top: u32, _bottom: u32, _opts: &BasicOpts) -> Vec<Range<usize>> {
+        let all = 0..self.cells().len();
+        vec![all]
+    }
 }
 /// Abstraction of rendering options to be used with different rendering methods.
 /// E.g., a map of distances (from a given starting point) will require changing the text label
@@ -151,6 +191,10 @@ pub trait RendererOps<'f> {
     ) {
     }
     fn render_frame(&self) -> RgbaImage {
+        self.render_frame_rows(0, self.dimensions().1)
+    }
+    /// The pixel rows `top..top + height` of [`render_frame`](RendererOps::render_frame).
+    fn render_frame_rows(&self, top: u32, height: u32) -> RgbaImage {
         let opts = self.options();
         let (x, y) = self.grid().image_dimensions(opts);
         let frame = opts.frame_size();
@@ -159,9 +203,9 @@ pub trait RendererOps<'f> {
         } else {
             opts.frame_color()
         };
-        let mut image = RgbaImage::from_pixel(x, y, *color);
+        let mut image = RgbaImage::from_pixel(x, height, *color);
         if frame != 0 {
-            let rect = Rect::at((opts.frame_size()) as i32, (opts.frame_size()) as i32)
+            let rect = Rect::at(frame as i32, frame as i32 - top as i32)
                 .of_size(x - (frame * 2), y - (frame * 2));
             draw_filled_rect_mut(&mut image, rect, *opts.bg_color());
         }
@@ -187,4 +231,90 @@ pub trait RendererOps<'f> {
             self.render_extra(id, &block, image);
         }
     }
+    /// Renders the pixel rows `top..top + height` of [`render_grid`](RendererOps::render_grid)
+    /// without drawing the rest of the image.
+    ///
+    /// Blocks may draw over their neighbors (walls are shared and labels can spill out), so every
+    /// block that can reach the band (see [`block_reach`]) is drawn, in the same order as
+    /// [`render_grid_blocks`](RendererOps::render_grid_blocks), onto an image starting above the
+    /// band.  The band cut out of it is identical to the same rows of the full render.
+    fn render_band(&self, top: u32, height: u32) -> RgbaImage {
+        let opts = self.options();
+        let width = self.dimensions().0;
+        let (up, down) = block_reach(opts);
+        let bottom = top + height;
+        // blocks drawn here start more than `up` below `start`, so shifting them up by `start`
+        // keeps every coordinate positive and the drawing identical, while anything drawn below
+        // the band is simply clipped
+        let start = top.saturating_sub(down + up);
+        let mut image = self.render_frame_rows(start, bottom - start);
+        let cells = self
+            .grid()
+            .band_cells(top.saturating_sub(down), bottom + up, opts);
+        for i in cells.into_iter().flat_map(|r| &self.grid().cells()[r]) {
+            let id = i.id();
+            let block = self.block_coords(id);
+            let y1 = block.rows().0;
+            if y1 >= bottom + up || y1 + down <= top {
+                continue;
+            }
+            let block = block.shift_up(start);
+            self.grid().render_block(
+                id,
+                &block,
+                &self.block_label(id),
+                &self.block_bg(id),
+                &mut image,
+                self.options(),
+            );
+            self.render_extra(id, &block, &mut image);
+        }
+        image::imageops::crop_imm(&image, 0, top - start, width, height).to_image()
+    }
+    /// Renders the same image as [`render_grid`](RendererOps::render_grid), splitting it into
+    /// horizontal bands that are drawn concurrently with [`render_band`](RendererOps::render_band).
+    #[cfg(feature = "rayon")]
+    fn render_grid_parallel(&self) -> RgbaImage
+    where
+        Self: Sync,
+    {
+        use rayon::prelude::*;
+        let opts = self.options();
+        let (width, height) = self.dimensions();
+        // a few bands per thread balances the load, while bands much shorter than a block would
+        // mostly redraw the blocks around them
+        let pitch = opts.block_size() + opts.border_width();
+        let threads = rayon::current_num_threads() as u32 * 4;
+        let band = height.div_ceil(threads).max(8 * pitch).max(1);
+        let mut buf = vec![0; width as usize * height as usize * 4];
+        buf.par_chunks_mut(band as usize * width as usize * 4)
+            .enumerate()
+            .for_each(|(i, chunk)| {
+                let top = i as u32 * band;
+                let rows = band.min(height - top);
+                chunk.copy_from_slice(&self.render_band(top, rows));
+            });
+        RgbaImage::from_raw(width, height, buf).unwrap()
+    }
+}
+
+/// How far above and below the top row of its block a block can be drawn, in pixels.
+///
+/// Walls, joints and filled passages stay within a block of their own, but labels start at the
+/// block padding (or around the center of the block) and run for the height of the font, so with
+/// a large font they can cover several blocks.  Glyphs are allowed to rise a whole font height
+/// above the point they are drawn at and to descend twice the font height below it.
+pub(in crate) fn block_reach(opts: &BasicOpts) -> (u32, u32) {
+    let pitch = opts.block_size() + opts.border_width();
+    if !opts.text_labels() {
+        return (pitch, 2 * pitch);
+    }
+    let start = match opts.center_labels() {
+        true => (opts.block_size() / 2) as i32 + opts.label_offset() - opts.font_size() as i32 / 2,
+        false => opts.block_padding() as i32,
+    };
+    let font = opts.font_x().max(opts.font_y()).ceil() as i32;
+    let up = (font - start).max(0) as u32;
+    let down = (start + 2 * font).max(0) as u32;
+    (up.max(pitch), down.max(2 * pitch))
 }
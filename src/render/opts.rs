@@ -2,6 +2,7 @@ use crate::render::DEJAVU_BYTES;
 use image::{Rgba, RgbaImage};
 use rusttype::{Font, Scale};
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// If resizing the image is desired [`ScaleImage`] will resize the image using
 /// with a given scaling factor (an `f32`) and
 /// [`image::imageops::FilterType`](https://docs.rs/image/latest/image/imageops/enum.FilterType.html).
@@ -43,22 +44,36 @@ impl ScaleImage {
         Self::CatmullRom(scale)
     }
 }
+/// Serializing requires the `serde` feature.  The font is not serialized; deserialized options
+/// use the default font, and any missing fields use their default values.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct BasicOpts<'f> {
     /// Background color of the grid (inside of frame).
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
     bg_color: Rgba<u8>,
     /// Not sure if this will be used yet :/
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
     fg_color: Rgba<u8>,
     /// Color of joint intersections (if `show_joints` is `true`).
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
     joint_color: Rgba<u8>,
     /// Text color fof the labels.
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
     label_color: Rgba<u8>,
     /// Color for the image frame.
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
     frame_color: Rgba<u8>,
     /// The default background color for grid blocks.
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
     block_color: Rgba<u8>,
     /// The color of a block's borders.
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
     border_color: Rgba<u8>,
+    /// The color used to mark passages that wrap around to the opposite edge of the grid.
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    wrap_color: Rgba<u8>,
     border_width: u32,
     /// How much padding should be added around the grid.
     /// This is used to increase visibility of the outside grid borders, which may be hard to see
@@ -75,6 +90,10 @@ pub struct BasicOpts<'f> {
     block_padding: u32,
     /// The length of each section of a joint.  The actual size of each joint will be double this value.
     joint_size: u32,
+    /// How far the walls of a weave crossing are inset from the edges of the block.  The passage
+    /// running over the crossing is drawn between these walls, while the tunnel beneath it is only
+    /// visible in the gaps left at the block edges.
+    weave_inset: u32,
     /// When `true` this will draw joints with pointed edges instead of square.
     /// Using `tri_joints` is only visibile when `border_width > 1`.
     ///
@@ -95,6 +114,7 @@ pub struct BasicOpts<'f> {
     /// Font to use when rendering text labels.
     ///
     /// See [rusttype](https://docs.rs/crate/rusttype) for more information.
+    #[cfg_attr(feature = "serde", serde(skip))]
     font: Font<'f>,
     /// This determines the size of the font when rendering.  See [rusttype] for more information.
     font_size: f32,
@@ -126,12 +146,14 @@ impl<'f> Default for BasicOpts<'f> {
             frame_color: Rgba([192u8, 192u8, 192u8, 255u8]),
             block_color: Rgba([220u8, 220u8, 220u8, 255u8]),
             border_color: Rgba([0u8, 0u8, 0u8, 255u8]),
+            wrap_color: Rgba([0u8, 128u8, 255u8, 255u8]),
             border_width: 1,
             frame_size: 40,
             block_size: 70,
             fill_empty_passages: true,
             block_padding: 2,
             joint_size: 6,
+            weave_inset: 12,
             tri_joints: false,
             show_joints: true,
             text_labels: true,
@@ -188,6 +210,9 @@ impl<'f> BasicOpts<'f> {
     pub fn border_color(&self) -> &Rgba<u8> {
         &self.border_color
     }
+    pub fn wrap_color(&self) -> &Rgba<u8> {
+        &self.wrap_color
+    }
     pub fn border_width(&self) -> u32 {
         self.border_width
     }
@@ -206,6 +231,9 @@ impl<'f> BasicOpts<'f> {
     pub fn joint_size(&self) -> u32 {
         self.joint_size
     }
+    pub fn weave_inset(&self) -> u32 {
+        self.weave_inset
+    }
     pub fn tri_joints(&self) -> bool {
         self.tri_joints
     }
@@ -257,6 +285,9 @@ impl<'f> BasicOpts<'f> {
     pub fn set_border_color(&mut self, color: Rgba<u8>) {
         self.border_color = color;
     }
+    pub fn set_wrap_color(&mut self, color: Rgba<u8>) {
+        self.wrap_color = color;
+    }
     pub fn set_border_width(&mut self, size: u32) {
         self.border_width = size;
     }
@@ -275,6 +306,9 @@ impl<'f> BasicOpts<'f> {
     pub fn set_joint_size(&mut self, size: u32) {
         self.joint_size = size;
     }
+    pub fn set_weave_inset(&mut self, size: u32) {
+        self.weave_inset = size;
+    }
     pub fn set_tri_joints(&mut self, show: bool) {
         self.tri_joints = show;
     }
@@ -312,3 +346,16 @@ impl<'f> BasicOpts<'f> {
         }
     }
 }
+
+/// Stores colors as `[r, g, b, a]` arrays, since `image` does not implement serde for them.
+#[cfg(feature = "serde")]
+mod rgba {
+    use image::Rgba;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    pub(super) fn serialize<S: Serializer>(color: &Rgba<u8>, s: S) -> Result<S::Ok, S::Error> {
+        color.0.serialize(s)
+    }
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Rgba<u8>, D::Error> {
+        <[u8; 4]>::deserialize(d).map(Rgba)
+    }
+}
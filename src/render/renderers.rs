@@ -3,7 +3,9 @@ pub mod custom;
 pub mod dist_map;
 pub mod grid;
 pub mod path_map;
+pub mod region_map;
 pub use dist_map::{DistMapOpts, DistMapRenderer, DistMask, DistText};
 pub use grid::RenderGrid;
 pub use path_map::{PathMapOpts, PathMapRenderer};
+pub use region_map::{RegionMapOpts, RegionMapRenderer, RegionText};
 // pub use heat::{HeatMapOpts, HeatMask, HeatText, HeatmapRenderer};
@@ -1,6 +1,6 @@
 use crate::maze::{Grid, GridProps};
 use crate::render::renderers::custom::{CustomFunc, CustomRenderer};
-use crate::render::{Renderable, Renderer, RendererOps};
+use crate::render::{Renderable, Renderer};
 use crate::util::Index;
 use image::{Rgba, RgbaImage};
 use linked_hash_set::LinkedHashSet;
@@ -30,7 +30,6 @@ pub struct Animation<'a, 'f, R: Renderer<'f>> {
     anim: Cow<'a, AnimOpts>,
     /// how long to show each frame
     enc: RefCell<Encoder>,
-    frame: RefCell<usize>,
     counter: RefCell<i32>,
     phantom: std::marker::PhantomData<&'f R>,
 }
@@ -43,8 +42,7 @@ impl<'a, 'f, R: Renderer<'f>> Animation<'a, 'f, R> {
             ),
             renderer,
             counter: RefCell::from(0),
-            frame: RefCell::from(0),
-            anim: opts.map(|a| Cow::Borrowed(a)).unwrap_or_else(Cow::default),
+            anim: opts.map(Cow::Borrowed).unwrap_or_default(),
             phantom: std::marker::PhantomData,
         }
     }
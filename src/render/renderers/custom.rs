@@ -3,8 +3,7 @@ use crate::util::Index;
 use image::{Rgba, RgbaImage};
 use std::collections::HashMap;
 
-pub type CustomFunc<R: Renderable> =
-    fn(&R, Index, &<<R as RendererOps>::G as Renderable>::B, &mut RgbaImage);
+pub type CustomFunc<R> = fn(&R, Index, &<<R as RendererOps>::G as Renderable>::B, &mut RgbaImage);
 
 pub struct CustomRenderer<
     'f,
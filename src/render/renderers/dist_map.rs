@@ -1,5 +1,5 @@
-use crate::maze::sq::SqGrid;
-use crate::maze::Grid;
+use crate::maze::sq::SqGridOf;
+use crate::maze::{Grid, LinkStore};
 use crate::render::blocks::UnsignedIntBlock;
 use crate::render::{BasicOpts, Renderable, Renderer, RendererOps};
 use crate::util::dist::Distances;
@@ -32,9 +32,9 @@ impl<'f, 'g, 'm, G: Grid + Renderable + Clone> DistMapRenderer<'f, 'g, 'm, G> {
         }
     }
 }
-impl<'f, 'o, 'g, 'm> Renderer<'f> for DistMapRenderer<'f, 'g, 'm, SqGrid> {}
-impl<'f, 'o, 'g, 'm> RendererOps<'f> for DistMapRenderer<'f, 'g, 'm, SqGrid> {
-    type G = SqGrid;
+impl<'f, 'g, 'm, L: LinkStore> Renderer<'f> for DistMapRenderer<'f, 'g, 'm, SqGridOf<L>> {}
+impl<'f, 'g, 'm, L: LinkStore> RendererOps<'f> for DistMapRenderer<'f, 'g, 'm, SqGridOf<L>> {
+    type G = SqGridOf<L>;
     fn options<'a>(&'a self) -> &'a BasicOpts<'f> {
         &self.opts
     }
@@ -61,7 +61,7 @@ impl<'f, 'o, 'g, 'm> RendererOps<'f> for DistMapRenderer<'f, 'g, 'm, SqGrid> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct DistMapOpts {
     pub mask: DistMask,
     pub text: DistText,
@@ -80,14 +80,6 @@ impl DistMapOpts {
         self.text = DistText::Dist;
     }
 }
-impl Default for DistMapOpts {
-    fn default() -> Self {
-        Self {
-            mask: DistMask::default(),
-            text: DistText::default(),
-        }
-    }
-}
 
 #[derive(Clone, Debug)]
 pub struct DistMask {
@@ -187,9 +179,10 @@ impl std::ops::Index<RgbChannel> for DistMask {
         }
     }
 }
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub enum DistText {
     Id,
+    #[default]
     Dist,
 }
 impl DistText {
@@ -200,11 +193,6 @@ impl DistText {
         Self::Dist
     }
 }
-impl Default for DistText {
-    fn default() -> Self {
-        Self::Dist
-    }
-}
 
 #[allow(clippy::many_single_char_names)]
 pub(in crate) fn calc_bg(dist: usize, max: f32, mask: &DistMask) -> Rgba<u8> {
@@ -247,4 +235,45 @@ mod tests {
             .render_options(Some(basic), Some(dist_opts))
             .save_render(std::path::Path::new("sq_distances_options.png"))
     }
+    #[test]
+    fn render_bands_with_large_labels() {
+        use crate::render::RendererOps;
+        let grid = new_maze(8);
+        let dist = grid.distances(Index::zero());
+        // labels several blocks tall, drawn from the top left and from above the center
+        let mut opts = BasicOpts::debug();
+        opts.set_block_size(12);
+        opts.set_font_size(60.0);
+        opts.set_font_x(60.0);
+        opts.set_font_y(60.0);
+        for center in [false, true] {
+            opts.set_center_labels(center);
+            opts.set_label_offset(if center { -40 } else { 0 });
+            let renderer = dist.render_options(Some(opts.clone()), None);
+            let expected = renderer.render_rgba();
+            let mut rows = Vec::new();
+            for top in (0..expected.height()).step_by(5) {
+                let height = 5.min(expected.height() - top);
+                rows.extend_from_slice(&renderer.render_band(top, height));
+            }
+            assert!(rows == expected.into_raw());
+        }
+    }
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn render_parallel() {
+        use crate::maze::sq::SyncSqGrid;
+        use crate::maze::Topology;
+        use rand::SeedableRng;
+        use rand_xoshiro::SplitMix64;
+        let mut rng = SplitMix64::seed_from_u64(49);
+        let grid = SyncSqGrid::with_topology(40, Topology::Torus);
+        grid.carve_wilsons(&mut rng);
+        let dist = grid.distances(Index::zero());
+        for opts in [BasicOpts::default(), BasicOpts::debug()] {
+            let renderer = dist.render_options(Some(opts), None);
+            let expected = renderer.render_rgba();
+            assert!(expected == renderer.render_rgba_parallel());
+        }
+    }
 }
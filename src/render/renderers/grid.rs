@@ -1,5 +1,9 @@
-use crate::maze::sq::SqGrid;
-use crate::maze::Grid;
+use crate::maze::cube::CubeGrid;
+use crate::maze::levels::LevelGrid;
+use crate::maze::sq::compact::CompactSqGrid;
+use crate::maze::sq::SqGridOf;
+use crate::maze::weave::WeaveGrid;
+use crate::maze::{Grid, LinkStore};
 use crate::render::blocks::UnsignedIntBlock;
 use crate::render::renderers::anim::{AnimOpts, Animation};
 use crate::render::{BasicOpts, Renderable, Renderer, RendererOps};
@@ -11,40 +15,116 @@ pub struct RenderGrid<'f, 'o, 'g, G: Grid + Renderable> {
     grid: &'g G,
     opts: Cow<'o, BasicOpts<'f>>,
 }
-impl<'f, 'o, 'g> Renderer<'f> for RenderGrid<'f, 'o, 'g, SqGrid> {}
-impl<'f, 'o, 'g> RenderGrid<'f, 'o, 'g, SqGrid> {
-    pub(in crate) fn new(grid: &'g SqGrid) -> Self {
+impl<'f, 'o, 'g, L: LinkStore> Renderer<'f> for RenderGrid<'f, 'o, 'g, SqGridOf<L>> {}
+impl<'f, 'o, 'g> Renderer<'f> for RenderGrid<'f, 'o, 'g, WeaveGrid> {}
+impl<'f, 'o, 'g> Renderer<'f> for RenderGrid<'f, 'o, 'g, LevelGrid> {}
+impl<'f, 'o, 'g> Renderer<'f> for RenderGrid<'f, 'o, 'g, CubeGrid> {}
+impl<'f, 'o, 'g> Renderer<'f> for RenderGrid<'f, 'o, 'g, CompactSqGrid> {}
+impl<'f, 'o, 'g, G: Grid + Renderable> RenderGrid<'f, 'o, 'g, G> {
+    pub(in crate) fn new(grid: &'g G) -> Self {
         Self {
             grid,
             opts: Cow::Owned(BasicOpts::default()),
         }
     }
-    pub(in crate) fn with_options(grid: &'g SqGrid, opts: &'o BasicOpts<'f>) -> Self {
+    pub(in crate) fn with_options(grid: &'g G, opts: &'o BasicOpts<'f>) -> Self {
         Self {
             grid,
             opts: Cow::Borrowed(opts),
         }
     }
-    pub fn animation<'a>(self, anim: Option<&'a AnimOpts>) -> Animation<'a, 'f, Self> {
+    pub fn animation<'a>(self, anim: Option<&'a AnimOpts>) -> Animation<'a, 'f, Self>
+    where
+        Self: Renderer<'f>,
+    {
         Animation::new(self, anim)
     }
-    pub fn animation_defaults<'a>(self) -> Animation<'a, 'f, Self> {
+    pub fn animation_defaults<'a>(self) -> Animation<'a, 'f, Self>
+    where
+        Self: Renderer<'f>,
+    {
         Animation::new(self, None)
     }
-    pub fn animation_options<'a>(self, anim: &'a AnimOpts) -> Animation<'a, 'f, Self> {
+    pub fn animation_options<'a>(self, anim: &'a AnimOpts) -> Animation<'a, 'f, Self>
+    where
+        Self: Renderer<'f>,
+    {
         Animation::new(self, Some(anim))
     }
     // pub fn customize(self, )
 }
-impl<'f, 'o, 'g> RendererOps<'f> for RenderGrid<'f, 'o, 'g, SqGrid> {
-    type G = SqGrid;
+impl<'f, 'o, 'g, L: LinkStore> RendererOps<'f> for RenderGrid<'f, 'o, 'g, SqGridOf<L>> {
+    type G = SqGridOf<L>;
     fn options<'a>(&'a self) -> &'a BasicOpts<'f> {
         &self.opts
     }
     fn options_mut<'a>(&'a mut self) -> &'a mut BasicOpts<'f> {
         self.opts.to_mut()
     }
-    fn grid(&self) -> &SqGrid {
+    fn grid(&self) -> &SqGridOf<L> {
+        self.grid
+    }
+    fn block_coords(&self, id: Index) -> <Self::G as Renderable>::B {
+        UnsignedIntBlock::new(self.grid, id, &self.opts)
+    }
+}
+
+impl<'f, 'o, 'g> RendererOps<'f> for RenderGrid<'f, 'o, 'g, WeaveGrid> {
+    type G = WeaveGrid;
+    fn options<'a>(&'a self) -> &'a BasicOpts<'f> {
+        &self.opts
+    }
+    fn options_mut<'a>(&'a mut self) -> &'a mut BasicOpts<'f> {
+        self.opts.to_mut()
+    }
+    fn grid(&self) -> &WeaveGrid {
+        self.grid
+    }
+    fn block_coords(&self, id: Index) -> <Self::G as Renderable>::B {
+        UnsignedIntBlock::new(self.grid, id, &self.opts)
+    }
+}
+impl<'f, 'o, 'g> RendererOps<'f> for RenderGrid<'f, 'o, 'g, LevelGrid> {
+    type G = LevelGrid;
+    fn options<'a>(&'a self) -> &'a BasicOpts<'f> {
+        &self.opts
+    }
+    fn options_mut<'a>(&'a mut self) -> &'a mut BasicOpts<'f> {
+        self.opts.to_mut()
+    }
+    fn grid(&self) -> &LevelGrid {
+        self.grid
+    }
+    fn block_coords(&self, id: Index) -> <Self::G as Renderable>::B {
+        self.grid.block_coords(id, &self.opts)
+    }
+}
+
+impl<'f, 'o, 'g> RendererOps<'f> for RenderGrid<'f, 'o, 'g, CubeGrid> {
+    type G = CubeGrid;
+    fn options<'a>(&'a self) -> &'a BasicOpts<'f> {
+        &self.opts
+    }
+    fn options_mut<'a>(&'a mut self) -> &'a mut BasicOpts<'f> {
+        self.opts.to_mut()
+    }
+    fn grid(&self) -> &CubeGrid {
+        self.grid
+    }
+    fn block_coords(&self, id: Index) -> <Self::G as Renderable>::B {
+        UnsignedIntBlock::new(self.grid, id, &self.opts)
+    }
+}
+
+impl<'f, 'o, 'g> RendererOps<'f> for RenderGrid<'f, 'o, 'g, CompactSqGrid> {
+    type G = CompactSqGrid;
+    fn options<'a>(&'a self) -> &'a BasicOpts<'f> {
+        &self.opts
+    }
+    fn options_mut<'a>(&'a mut self) -> &'a mut BasicOpts<'f> {
+        self.opts.to_mut()
+    }
+    fn grid(&self) -> &CompactSqGrid {
         self.grid
     }
     fn block_coords(&self, id: Index) -> <Self::G as Renderable>::B {
@@ -31,7 +31,7 @@ impl<'f, 'g, 'p> PathMapRenderer<'f, 'g, 'p, SqGrid> {
         }
     }
 }
-impl<'f, 'o, 'g, 'm> Renderer<'f> for PathMapRenderer<'f, 'g, 'm, SqGrid> {}
+impl<'f, 'g, 'm> Renderer<'f> for PathMapRenderer<'f, 'g, 'm, SqGrid> {}
 impl<'f, 'g, 'p> RendererOps<'f> for PathMapRenderer<'f, 'g, 'p, SqGrid> {
     type G = SqGrid;
     fn options<'a>(&'a self) -> &'a BasicOpts<'f> {
@@ -0,0 +1,178 @@
+use crate::maze::sq::SqGrid;
+use crate::maze::Grid;
+use crate::render::blocks::UnsignedIntBlock;
+use crate::render::{BasicOpts, Renderable, Renderer, RendererOps};
+use crate::util::regions::Regions;
+use crate::util::Index;
+use image::Rgba;
+use log::trace;
+use std::borrow::Cow;
+
+/// Colors each cell by the seed it is closest to, fading towards white further from the seed.
+#[derive(Clone, Debug)]
+pub struct RegionMapRenderer<'f, 'g, 'm, G: Grid + Renderable + Clone> {
+    grid: &'g G,
+    opts: BasicOpts<'f>,
+    settings: RegionMapOpts,
+    map: Cow<'m, Regions<'g, G>>,
+    max: usize,
+}
+impl<'f, 'g, 'm, G: Grid + Renderable + Clone> RegionMapRenderer<'f, 'g, 'm, G> {
+    pub(in crate) fn new(
+        map: &'m Regions<'g, G>,
+        opts: Option<BasicOpts<'f>>,
+        settings: Option<RegionMapOpts>,
+    ) -> Self {
+        Self {
+            grid: map.grid(),
+            opts: opts.unwrap_or_default(),
+            settings: settings.unwrap_or_default(),
+            max: map.max_dist().map_or(0, |(_, dist)| dist),
+            map: Cow::Borrowed(map),
+        }
+    }
+}
+impl<'f, 'g, 'm> Renderer<'f> for RegionMapRenderer<'f, 'g, 'm, SqGrid> {}
+impl<'f, 'g, 'm> RendererOps<'f> for RegionMapRenderer<'f, 'g, 'm, SqGrid> {
+    type G = SqGrid;
+    fn options<'a>(&'a self) -> &'a BasicOpts<'f> {
+        &self.opts
+    }
+    fn options_mut<'a>(&'a mut self) -> &'a mut BasicOpts<'f> {
+        &mut self.opts
+    }
+    fn block_label(&self, id: Index) -> String {
+        match self.settings.text {
+            RegionText::Dist => self.map.get(id).map(|d| d.to_string()),
+            RegionText::Seed => self.map.owner(id).map(|s| s.to_string()),
+        }
+        .unwrap_or_default()
+    }
+    fn block_bg(&self, id: Index) -> Rgba<u8> {
+        match (self.map.get(id), self.map.owner_index(id)) {
+            (Some(dist), Some(seed)) => calc_region_bg(
+                // regions are drawn in the block color when there is no palette
+                self.settings
+                    .color(seed)
+                    .unwrap_or_else(|| self.opts.block_color()),
+                dist,
+                self.max as f32,
+                self.settings.fade,
+            ),
+            _ => {
+                trace!("Could not find region for id={}", id);
+                *self.opts.block_color()
+            }
+        }
+    }
+    fn grid(&self) -> &Self::G {
+        self.grid
+    }
+    fn block_coords(&self, id: Index) -> <Self::G as Renderable>::B {
+        UnsignedIntBlock::new(self.grid, id, &self.opts)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RegionMapOpts {
+    /// Colors used for the regions, repeated if there are more seeds than colors.
+    pub palette: Vec<Rgba<u8>>,
+    /// How far the color fades towards white at the maximum distance, from `0.0` to `1.0`.
+    pub fade: f32,
+    pub text: RegionText,
+}
+impl Default for RegionMapOpts {
+    fn default() -> Self {
+        Self {
+            palette: vec![
+                Rgba([228, 26, 28, 255]),
+                Rgba([55, 126, 184, 255]),
+                Rgba([77, 175, 74, 255]),
+                Rgba([152, 78, 163, 255]),
+                Rgba([255, 127, 0, 255]),
+                Rgba([166, 86, 40, 255]),
+                Rgba([247, 129, 191, 255]),
+                Rgba([153, 153, 153, 255]),
+            ],
+            fade: 0.8,
+            text: RegionText::default(),
+        }
+    }
+}
+impl RegionMapOpts {
+    /// The color of the region belonging to the seed at position `seed`, or `None` if the palette
+    /// is empty.
+    pub fn color(&self, seed: usize) -> Option<&Rgba<u8>> {
+        self.palette.get(seed.checked_rem(self.palette.len())?)
+    }
+    pub fn show_seed(&mut self) {
+        self.text = RegionText::Seed;
+    }
+    pub fn show_dist(&mut self) {
+        self.text = RegionText::Dist;
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum RegionText {
+    /// Label cells with the id of the closest seed.
+    Seed,
+    /// Label cells with the distance to the closest seed.
+    #[default]
+    Dist,
+}
+
+pub(in crate) fn calc_region_bg(color: &Rgba<u8>, dist: usize, max: f32, fade: f32) -> Rgba<u8> {
+    let i = match max > 0.0 {
+        true => fade * dist as f32 / max,
+        false => 0.0,
+    };
+    let calc = |c: u8| -> u8 { (c as f32 + (255.0 - c as f32) * i) as u8 };
+    Rgba([calc(color[0]), calc(color[1]), calc(color[2]), color[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::sq::tests::new_maze;
+    use crate::maze::Grid;
+    use crate::render::Renderer;
+    #[test]
+    fn render_regions() -> Result<(), image::ImageError> {
+        let grid = new_maze(5);
+        let mut opts = RegionMapOpts::default();
+        opts.show_seed();
+        grid.regions(&[0.into(), 24.into(), 12.into()])
+            .render_options(None, Some(opts))
+            .save_render(std::path::Path::new("regions.png"))
+    }
+    #[test]
+    fn empty_palette() {
+        let grid = new_maze(3);
+        let opts = RegionMapOpts {
+            palette: Vec::new(),
+            ..RegionMapOpts::default()
+        };
+        assert_eq!(opts.color(0), None);
+        let image = grid
+            .regions(&[0.into(), 8.into()])
+            .render_options(None, Some(opts))
+            .render_rgba();
+        let block = UnsignedIntBlock::new(&grid, 0.into(), &BasicOpts::default());
+        assert_eq!(
+            image.get_pixel(block.x1 + 1, block.y1 + 1),
+            BasicOpts::default().block_color()
+        );
+    }
+    #[test]
+    fn render_without_seeds() {
+        let grid = new_maze(3);
+        let regions = grid.regions(&[]);
+        assert_eq!(regions.max_dist(), None);
+        let image = regions.render_defaults().render_rgba();
+        assert_eq!(
+            image.dimensions(),
+            grid.render_defaults().render_rgba().dimensions()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Writing renders as PNG images one strip of rows at a time.
+//!
+//! A 20,000×20,000 cell poster can take hundreds of gigabytes as a single [`RgbaImage`](image::RgbaImage).
+//! [`Renderer::save_render_streamed`](crate::render::Renderer::save_render_streamed) instead
+//! renders horizontal strips with [`RendererOps::render_band`] and feeds their rows to a
+//! [`PngStream`], so only one strip is held in memory at a time.
+
+use crate::render::RendererOps;
+use deflate::write::ZlibEncoder;
+use deflate::Compression;
+use std::io::Write;
+
+/// The largest size of a strip of rows, in bytes, before it is written out.
+const STRIP_BYTES: usize = 1 << 26;
+/// The size of the `IDAT` chunks the compressed image data is split into.
+const CHUNK_BYTES: usize = 1 << 16;
+/// The PNG `Sub` filter, which stores each byte as the difference to the pixel on its left.
+const FILTER_SUB: u8 = 1;
+
+/// Encodes an RGBA PNG image whose rows are written one after another.
+pub struct PngStream<W: Write> {
+    zlib: ZlibEncoder<IdatWriter<W>>,
+    width: u32,
+    rows_left: u32,
+    row: Vec<u8>,
+}
+impl<W: Write> PngStream<W> {
+    /// Writes the PNG header for a `width`×`height` image.
+    pub fn new(w: W, width: u32, height: u32) -> Result<Self, image::ImageError> {
+        let mut encoder = png::Encoder::new(w, width, height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let writer = encoder.write_header().map_err(std::io::Error::from)?;
+        Ok(Self {
+            zlib: ZlibEncoder::new(IdatWriter::new(writer), Compression::Default),
+            width,
+            rows_left: height,
+            row: Vec::with_capacity(width as usize * 4 + 1),
+        })
+    }
+    /// Writes whole rows of RGBA pixels, continuing below the rows written before.
+    ///
+    /// Panics if `pixels` is not made of whole rows or holds more rows than are left.
+    pub fn write_rows(&mut self, pixels: &[u8]) -> std::io::Result<()> {
+        let len = self.width as usize * 4;
+        assert_eq!(pixels.len() % len, 0, "pixels must hold whole rows");
+        let rows = (pixels.len() / len) as u32;
+        assert!(rows <= self.rows_left, "more rows than the image height");
+        self.rows_left -= rows;
+        for line in pixels.chunks(len) {
+            self.row.clear();
+            self.row.push(FILTER_SUB);
+            self.row.extend_from_slice(&line[..4]);
+            self.row.extend(
+                line.iter()
+                    .zip(line.iter().skip(4))
+                    .map(|(left, b)| b.wrapping_sub(*left)),
+            );
+            self.zlib.write_all(&self.row)?;
+        }
+        Ok(())
+    }
+    /// Finishes the image once every row has been written.
+    pub fn finish(self) -> Result<(), image::ImageError> {
+        if self.rows_left != 0 {
+            let msg = format!("{} rows of the image were not written", self.rows_left);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, msg).into());
+        }
+        self.zlib.finish()?.finish()
+    }
+}
+
+/// Splits the compressed image data into `IDAT` chunks.
+struct IdatWriter<W: Write> {
+    writer: png::Writer<W>,
+    buf: Vec<u8>,
+}
+impl<W: Write> IdatWriter<W> {
+    fn new(writer: png::Writer<W>) -> Self {
+        Self {
+            writer,
+            buf: Vec::with_capacity(CHUNK_BYTES),
+        }
+    }
+    fn write_chunk(&mut self) -> std::io::Result<()> {
+        if !self.buf.is_empty() {
+            self.writer.write_chunk(png::chunk::IDAT, &self.buf)?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+    /// Writes the last `IDAT` chunk, after which dropping the [`png::Writer`] adds the `IEND`
+    /// chunk.
+    fn finish(mut self) -> Result<(), image::ImageError> {
+        self.write_chunk()?;
+        Ok(())
+    }
+}
+impl<W: Write> Write for IdatWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(CHUNK_BYTES - self.buf.len());
+        self.buf.extend_from_slice(&buf[..len]);
+        if self.buf.len() == CHUNK_BYTES {
+            self.write_chunk()?;
+        }
+        Ok(len)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The number of rows rendered at once: as many as fit in [`STRIP_BYTES`], but at least a block.
+pub(in crate) fn strip_height<'f, R: RendererOps<'f> + ?Sized>(renderer: &R) -> u32 {
+    let opts = renderer.options();
+    let row = renderer.dimensions().0 as usize * 4;
+    let rows = (STRIP_BYTES / row.max(1)) as u32;
+    rows.max(opts.block_size() + opts.border_width())
+}
+
+/// Renders the image in strips of `strip` rows and writes them to `w` as a PNG.
+pub(in crate) fn write_png<'f, R, W>(renderer: &R, w: W, strip: u32) -> Result<(), image::ImageError>
+where
+    R: RendererOps<'f> + ?Sized,
+    W: Write,
+{
+    let (width, height) = renderer.dimensions();
+    let mut png = PngStream::new(w, width, height)?;
+    for top in (0..height).step_by(strip.max(1) as usize) {
+        let band = renderer.render_band(top, strip.min(height - top));
+        png.write_rows(&band)?;
+    }
+    png.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::sq::tests::new_maze;
+    use crate::maze::Grid;
+    use crate::render::{BasicOpts, Renderer};
+    use crate::util::Index;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn stream_strips() -> Result<(), image::ImageError> {
+        let grid = new_maze(9);
+        let dist = grid.distances(Index::zero());
+        let renderer = dist.render_options(Some(BasicOpts::debug()), None);
+        let expected = renderer.render_rgba();
+        // strips shorter than a block, as well as ones spanning several
+        for strip in [7, 150, 10_000] {
+            let mut buf = Vec::new();
+            write_png(&renderer, &mut buf, strip)?;
+            let image = image::load_from_memory_with_format(&buf, image::ImageFormat::Png)?;
+            assert!(image.to_rgba8() == expected);
+        }
+        renderer.save_render_streamed(std::path::Path::new("streamed.png"))
+    }
+    /// Streams the render in strips of 7 rows and compares it with the PNG saved by
+    /// [`Renderer::save_render`].
+    fn assert_streams_saved<'f, R: Renderer<'f>>(
+        renderer: &R,
+        name: &str,
+    ) -> Result<(), image::ImageError> {
+        let path = std::path::Path::new(name);
+        renderer.save_render(path)?;
+        let saved = image::open(path)?.to_rgba8();
+        let mut buf = Vec::new();
+        write_png(renderer, &mut buf, 7)?;
+        let image = image::load_from_memory_with_format(&buf, image::ImageFormat::Png)?;
+        assert!(image.to_rgba8() == saved, "{}", name);
+        Ok(())
+    }
+    #[test]
+    fn stream_other_grids() -> Result<(), image::ImageError> {
+        use crate::maze::cube::CubeGrid;
+        use crate::maze::levels::LevelGrid;
+        use crate::maze::weave::WeaveGrid;
+        let mut rng = SplitMix64::seed_from_u64(50);
+        // labels spilling over the blocks around them
+        let mut opts = BasicOpts::debug();
+        opts.set_block_size(14);
+        opts.set_font_size(30.0);
+        opts.set_font_x(30.0);
+        opts.set_font_y(30.0);
+        let weave = WeaveGrid::kruskals_weave(6, &mut rng);
+        assert!(weave.iter().any(|c| c.is_under()));
+        assert_streams_saved(&weave.render_options(&opts), "streamed_weave.png")?;
+        let levels = LevelGrid::new(4, 3);
+        levels.carve_wilsons(&mut rng);
+        assert_streams_saved(&levels.render_options(&opts), "streamed_levels.png")?;
+        let cube = CubeGrid::new(3);
+        cube.carve_wilsons(&mut rng);
+        assert_streams_saved(&cube.render_options(&opts), "streamed_cube.png")?;
+        let grid = new_maze(6);
+        let dist = grid.distances(Index::zero());
+        let renderer = dist.render_options(Some(opts), None);
+        assert_streams_saved(&renderer, "streamed_dist.png")
+    }
+}
@@ -4,6 +4,7 @@ pub mod dist;
 pub mod index;
 pub mod ordinal;
 pub mod path;
+pub mod regions;
 pub mod row_size;
 pub mod visit;
 
@@ -31,6 +32,7 @@ impl Numerical for u32 {
 }
 
 #[derive(Clone, Copy, Debug, Display, Hash, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[display("(x={x},y={y})")]
 pub struct Coord {
     x: Index,
@@ -4,6 +4,7 @@ pub use crate::util::row_size::RowSize;
 use parse_display::{Display, FromStr};
 
 #[derive(Debug, Display, FromStr, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Cardinal {
     #[display("North")]
     N,
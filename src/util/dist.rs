@@ -1,4 +1,4 @@
-use crate::maze::{Cell, Grid};
+use crate::maze::{Cell, Grid, LinkStore};
 use crate::render::renderers::{DistMapOpts, DistMapRenderer};
 use crate::render::{BasicOpts, Renderable};
 use crate::util::path::Path;
@@ -56,12 +56,17 @@ where
         let root = self.root;
         while cur != root {
             let cell = self.grid.get(cur).expect("cell could not be retrieved");
-            for link in cell.links().borrow().iter() {
-                if self[*link] < self[cur] {
-                    path.push(*link);
-                    cur = *link;
-                }
-            }
+            // the closest linked cell is the previous step, for both hop counts and weights
+            let prev = cell
+                .links()
+                .borrow()
+                .iter()
+                .filter(|link| self.has_entry(link))
+                .min_by_key(|link| self[**link])
+                .copied()
+                .expect("cell is not connected to the root");
+            path.push(prev);
+            cur = prev;
         }
         path.reverse();
         Path::new(path, self.grid)
@@ -84,6 +89,33 @@ where
     }
 }
 
+/// [`Distances`] without the reference to the grid, e.g. for storing them on their own.
+/// Serializing requires the `serde` feature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedDistances {
+    pub root: Index,
+    pub map: HashMap<Index, usize>,
+}
+impl OwnedDistances {
+    /// Attaches the distances to the grid they were computed for.
+    pub fn with_grid<G: Grid>(self, grid: &G) -> Distances<'_, G> {
+        Distances {
+            grid,
+            root: self.root,
+            map: self.map,
+        }
+    }
+}
+impl<'g, G: Grid> From<&Distances<'g, G>> for OwnedDistances {
+    fn from(dist: &Distances<'g, G>) -> Self {
+        Self {
+            root: dist.root,
+            map: dist.map.clone(),
+        }
+    }
+}
+
 impl<'g, G: Grid> std::ops::Index<Index> for Distances<'g, G> {
     type Output = usize;
     fn index(&self, index: Index) -> &Self::Output {
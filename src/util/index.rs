@@ -6,6 +6,8 @@ use std::ops::{
 };
 
 #[derive(Clone, Copy, Debug, Default, Display, Hash, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Index(pub(in crate::util) usize);
 //  todo: impl Add/Sub/Mul/Div/AddAssign/SubAssign/MulAssign/DivAssign/Deref/Rem  Neg/Not??  From<Cell> - calls self.id
 impl Index {
@@ -3,6 +3,7 @@ use crate::util::{Coord, Horizontal, Index, RowSize, Vertical, Visit};
 use parse_display::{Display, FromStr};
 
 #[derive(Debug, Display, FromStr, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Ordinal {
     #[display("Northwest")]
     Nw,
@@ -30,7 +30,7 @@ impl<'g, G: Grid> Path<'g, G> {
         }
         string
     }
-    pub fn iter(&self) -> std::slice::Iter<Index> {
+    pub fn iter(&self) -> std::slice::Iter<'_, Index> {
         self.path.iter()
     }
     pub fn as_map(&self) -> HashMap<Index, usize> {
@@ -85,18 +85,38 @@ impl<'g, G: Grid> Path<'g, G> {
 impl<'g, G: Grid + CardinalGrid> Path<'g, G> {
     pub fn prev_dir(&self, id: Index) -> Option<Cardinal> {
         self.prev(id)
-            .map(|a| self.grid.dir_from(id, a))
-            .flatten()
+            .and_then(|a| self.grid.dir_from(id, a))
             .or_else(|| self.grid.find_boundary(id))
     }
     pub fn next_dir(&self, id: Index) -> Option<Cardinal> {
         self.next(id)
-            .map(|a| self.grid.dir_from(id, a))
-            .flatten()
+            .and_then(|a| self.grid.dir_from(id, a))
             .or_else(|| self.grid.find_boundary(id))
     }
 }
 
+/// A [`Path`] without the reference to the grid, e.g. for storing it on its own.  Serializing
+/// requires the `serde` feature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct OwnedPath {
+    pub path: Vec<Index>,
+}
+impl OwnedPath {
+    /// Attaches the path to the grid it belongs to.
+    pub fn with_grid<G: Grid>(self, grid: &G) -> Path<'_, G> {
+        Path::new(self.path, grid)
+    }
+}
+impl<'g, G: Grid> From<&Path<'g, G>> for OwnedPath {
+    fn from(path: &Path<'g, G>) -> Self {
+        Self {
+            path: path.path.clone(),
+        }
+    }
+}
+
 impl<'g, G: Grid> std::fmt::Display for Path<'g, G> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.string())
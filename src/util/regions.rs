@@ -0,0 +1,144 @@
+use crate::maze::{Cell, Grid, LinkStore};
+use crate::render::renderers::{RegionMapOpts, RegionMapRenderer};
+use crate::render::{BasicOpts, Renderable};
+use crate::util::Index;
+use std::collections::HashMap;
+
+/// Distances from several seeds at once.  Every reachable cell records its distance to the
+/// closest seed and which seed that is, dividing the grid into regions (a Voronoi diagram
+/// measured along the passages of the maze).
+///
+/// Cells that are equally close to more than one seed belong to the seed listed first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regions<'g, G: Grid> {
+    grid: &'g G,
+    seeds: Vec<Index>,
+    map: HashMap<Index, (usize, usize)>,
+}
+
+impl<'g, G> Regions<'g, G>
+where
+    G: Grid,
+{
+    /// Runs a breadth first search from all seeds at the same time.
+    pub fn new(grid: &'g G, seeds: &[Index]) -> Self {
+        let mut map = HashMap::new();
+        let mut frontier = Vec::new();
+        for (i, seed) in seeds.iter().enumerate() {
+            if !map.contains_key(seed) {
+                map.insert(*seed, (0, i));
+                frontier.push(*seed);
+            }
+        }
+        while !frontier.is_empty() {
+            let mut new_frontier: Vec<Index> = Vec::new();
+            for id in frontier.iter() {
+                let (dist, seed) = map[id];
+                for link in grid.lookup(*id).links().borrow().iter() {
+                    if !map.contains_key(link) {
+                        map.insert(*link, (dist + 1, seed));
+                        new_frontier.push(*link);
+                    }
+                }
+            }
+            frontier = new_frontier;
+        }
+        Self {
+            grid,
+            seeds: seeds.to_vec(),
+            map,
+        }
+    }
+    pub(in crate) fn grid(&self) -> &'g G {
+        self.grid
+    }
+    pub fn seeds(&self) -> &[Index] {
+        &self.seeds
+    }
+    /// Distance from the cell to the closest seed.
+    pub fn get(&self, id: Index) -> Option<usize> {
+        self.map.get(&id).map(|(dist, _)| *dist)
+    }
+    /// Position in [`Regions::seeds`] of the seed closest to the cell.
+    pub fn owner_index(&self, id: Index) -> Option<usize> {
+        self.map.get(&id).map(|(_, seed)| *seed)
+    }
+    /// The seed closest to the cell.
+    pub fn owner(&self, id: Index) -> Option<Index> {
+        self.owner_index(id).map(|i| self.seeds[i])
+    }
+    pub fn map_ref(&self) -> &HashMap<Index, (usize, usize)> {
+        &self.map
+    }
+    /// All cells that belong to the seed at position `seed` in [`Regions::seeds`].
+    pub fn region(&self, seed: usize) -> Vec<Index> {
+        let mut cells: Vec<Index> = self
+            .map
+            .iter()
+            .filter(|(_, (_, owner))| *owner == seed)
+            .map(|(id, _)| *id)
+            .collect();
+        cells.sort();
+        cells
+    }
+    /// Number of cells in each region, in the order of [`Regions::seeds`].
+    pub fn region_sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.seeds.len()];
+        self.map.values().for_each(|(_, seed)| sizes[*seed] += 1);
+        sizes
+    }
+    /// The cell furthest away from any seed and its distance, or `None` if there are no seeds.
+    pub fn max_dist(&self) -> Option<(Index, usize)> {
+        self.map
+            .iter()
+            .max_by(|(_, (a, _)), (_, (b, _))| a.cmp(b))
+            .map(|(id, (dist, _))| (*id, *dist))
+    }
+}
+
+impl<'g, G> Regions<'g, G>
+where
+    G: Grid + Renderable + Clone,
+{
+    pub fn render_defaults<'f, 'm>(&'m self) -> RegionMapRenderer<'f, 'g, 'm, G> {
+        RegionMapRenderer::new(self, Some(BasicOpts::default()), None)
+    }
+    pub fn render_options<'f, 'm>(
+        &'m self,
+        opts: Option<BasicOpts<'f>>,
+        region_opts: Option<RegionMapOpts>,
+    ) -> RegionMapRenderer<'f, 'g, 'm, G> {
+        RegionMapRenderer::new(self, opts, region_opts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::sq::SqGrid;
+    use crate::maze::{Grid, GridProps};
+    use crate::util::Index;
+    use rand::SeedableRng;
+    use rand_xoshiro::SplitMix64;
+    #[test]
+    fn closest_seed() {
+        let mut rng = SplitMix64::seed_from_u64(34);
+        let grid = SqGrid::wilsons(8, &mut rng);
+        let seeds: Vec<Index> = vec![0.into(), 63.into(), 36.into()];
+        let regions = grid.regions(&seeds);
+        assert_eq!(
+            regions.region_sizes().iter().sum::<usize>(),
+            *grid.capacity()
+        );
+        for (i, seed) in seeds.iter().enumerate() {
+            assert_eq!(regions.owner(*seed), Some(*seed));
+            assert!(regions.region(i).contains(seed));
+        }
+        let dists: Vec<_> = seeds.iter().map(|s| grid.distances(*s)).collect();
+        for id in (0..*grid.capacity()).map(Index::from) {
+            let closest = dists.iter().map(|d| d[id]).min().unwrap();
+            assert_eq!(regions.get(id), Some(closest));
+            let owner = regions.owner_index(id).unwrap();
+            assert_eq!(dists[owner][id], closest);
+        }
+    }
+}
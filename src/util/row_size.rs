@@ -9,6 +9,8 @@ use std::ops::{
 pub type ColSize = RowSize;
 
 #[derive(Clone, Copy, Debug, Display, Hash, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct RowSize(pub(in crate::util) usize);
 impl Numerical for RowSize {
     fn num(&self) -> usize {